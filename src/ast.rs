@@ -0,0 +1,192 @@
+//! Syntax tree produced by the grammar in [`crate::parse`].
+
+use std::fmt;
+
+use crate::Span;
+
+/// Prefix and postfix operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnaryOp {
+    /// `-x`
+    Neg,
+    /// `x!`, extended to non-integers through the gamma function
+    Factorial,
+    /// `x%`, only in [`ParseOptions::percent`](crate::ParseOptions::percent) mode
+    Percent,
+}
+
+/// Infix operators, grouped by grammar level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BinaryOp {
+    /// `+` (Expression level)
+    Add,
+    /// `-` (Expression level)
+    Sub,
+    /// `*` (Term level)
+    Mul,
+    /// `/` (Term level)
+    Div,
+    /// `%`, remainder with the sign of the left operand (Term level)
+    Rem,
+    /// `//`, division rounded down (Term level)
+    FloorDiv,
+    /// `^` or `**` (Power level)
+    Pow,
+}
+
+impl BinaryOp {
+    /// The operator as it is written in the source.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Rem => "%",
+            BinaryOp::FloorDiv => "//",
+            BinaryOp::Pow => "^",
+        }
+    }
+}
+
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+/// A parsed arithmetic expression.
+///
+/// Every node carries the [`Span`] of the source text it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expr {
+    /// A numeric literal such as `2` or `3.5`.
+    ///
+    /// `literal` is the source text, for evaluation modes more precise than `f64`.
+    Number {
+        value: f64,
+        literal: String,
+        span: Span,
+    },
+    /// A name such as `pi`, resolved when the expression is evaluated.
+    Ident { name: String, span: Span },
+    /// A prefix or postfix operator applied to an operand: `-x`, `x!`.
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+        span: Span,
+    },
+    /// Two operands joined by an infix operator: `x + y`.
+    Binary {
+        op: BinaryOp,
+        lhs: Box<Expr>,
+        rhs: Box<Expr>,
+        span: Span,
+    },
+    /// A function call such as `sqrt(x)`.
+    Call {
+        name: String,
+        args: Vec<Expr>,
+        span: Span,
+    },
+    /// An expression in brackets: `(x)`.
+    Group { inner: Box<Expr>, span: Span },
+}
+
+impl Expr {
+    /// Location of this node in the source.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Number { span, .. }
+            | Expr::Ident { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Binary { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Group { span, .. } => *span,
+        }
+    }
+
+    /// Numeric literals in the order they appear in the source.
+    pub fn operands(&self) -> Vec<f64> {
+        let mut out = Vec::new();
+        self.walk(&mut |e| {
+            if let Expr::Number { value, .. } = e {
+                out.push(*value);
+            }
+        });
+        out
+    }
+
+    /// Infix operators in the order they appear in the source.
+    pub fn operators(&self) -> Vec<BinaryOp> {
+        let mut out = Vec::new();
+        self.walk(&mut |e| {
+            if let Expr::Binary { op, .. } = e {
+                out.push(*op);
+            }
+        });
+        out
+    }
+
+    // in-order traversal, so the visiting order matches the source text
+    fn walk(&self, f: &mut impl FnMut(&Expr)) {
+        match self {
+            Expr::Number { .. } | Expr::Ident { .. } => f(self),
+            Expr::Unary { operand, .. } => {
+                f(self);
+                operand.walk(f);
+            }
+            Expr::Binary { lhs, rhs, .. } => {
+                lhs.walk(f);
+                f(self);
+                rhs.walk(f);
+            }
+            Expr::Call { args, .. } => {
+                f(self);
+                for arg in args {
+                    arg.walk(f);
+                }
+            }
+            Expr::Group { inner, .. } => {
+                f(self);
+                inner.walk(f);
+            }
+        }
+    }
+}
+
+/// One line of input: an assignment or a bare expression.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Statement {
+    /// `name = value`, e.g. `rate = 0.2`.
+    Assign {
+        name: String,
+        value: Expr,
+        span: Span,
+    },
+    /// `value == expected`, a line that states its own expected result.
+    ///
+    /// Its value is the value of `value`; comparing it with `expected` is left
+    /// to the caller, e.g. the `check` command.
+    Expect {
+        value: Expr,
+        expected: Expr,
+        span: Span,
+    },
+    /// An expression whose value is the result of the line.
+    Expr(Expr),
+}
+
+impl Statement {
+    /// Location of the whole statement in the source.
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Assign { span, .. } | Statement::Expect { span, .. } => *span,
+            Statement::Expr(expr) => expr.span(),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! The `check` command: expression files used as regression tests.
+
+use std::io::{self, BufRead};
+
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Diagnostic, ParseError, Session, Statement, parse, parse_statement, strip_comment,
+};
+
+use crate::read_lines;
+
+/// Evaluates every line and compares it with the value it expects, either
+/// `value == expected` or a trailing `# => expected` comment.
+///
+/// Lines without an expectation are still evaluated, so assignments take
+/// effect, and fail the check only when they fail to evaluate. Prints one
+/// `PASS` or `FAIL` line per check and a summary; returns whether all passed.
+pub fn run(reader: impl BufRead, origin: &str, tolerance: f64, strict: bool) -> io::Result<bool> {
+    let mut session = Session::new().tolerance(tolerance);
+    let mut passed = 0;
+    let mut failed = 0;
+    read_lines(reader, |number, line| {
+        let code = strip_comment(line);
+        if code.trim().is_empty() {
+            return true;
+        }
+        let source = code.trim_end();
+        let expected = expectation(line, code.len());
+        match check_line(&mut session, source, expected.as_deref()) {
+            Outcome::Evaluated => return true,
+            Outcome::Pass => {
+                passed += 1;
+                println!("PASS {}:{}: {}", origin, number, line.trim());
+            }
+            Outcome::Mismatch { actual, expected } => {
+                failed += 1;
+                println!(
+                    "FAIL {}:{}: {} = {}, expected {}",
+                    origin, number, source, actual, expected
+                );
+            }
+            Outcome::Error(error) => {
+                failed += 1;
+                println!("FAIL {}:{}: {}", origin, number, line.trim());
+                println!(
+                    "{}",
+                    Diagnostic::new(line, &error)
+                        .origin(origin)
+                        .first_line(number)
+                        .context(session.context())
+                );
+            }
+        }
+        failed == 0 || !strict
+    })?;
+    println!("{} passed, {} failed", passed, failed);
+    Ok(failed == 0)
+}
+
+enum Outcome {
+    /// A line without an expectation, evaluated successfully.
+    Evaluated,
+    Pass,
+    Mismatch {
+        actual: f64,
+        expected: f64,
+    },
+    /// The line or its expected value failed, with a span into the whole line.
+    Error(ParseError),
+}
+
+fn check_line(session: &mut Session, source: &str, comment: Option<&str>) -> Outcome {
+    let statement = match parse_statement(source) {
+        Ok(statement) => statement,
+        Err(error) => return Outcome::Error(error),
+    };
+
+    // the session compares the two sides of `==` itself; a `# =>` comment is
+    // evaluated before the line updates `ans`
+    let expects = matches!(statement, Statement::Expect { .. });
+    let expected = match comment {
+        Some(comment) if !expects => {
+            match parse(comment).and_then(|e| session.context().eval_with(&e, session.env())) {
+                Ok(value) => Some(value),
+                Err(error) => return Outcome::Error(error),
+            }
+        }
+        _ => None,
+    };
+
+    let actual = match session.execute(&statement) {
+        Ok(value) => value,
+        Err(ParseError::ExpectationFailed {
+            actual, expected, ..
+        }) => return Outcome::Mismatch { actual, expected },
+        Err(error) => return Outcome::Error(error),
+    };
+    match expected {
+        None if expects => Outcome::Pass,
+        None => Outcome::Evaluated,
+        Some(expected) if session.matches(actual, expected) => Outcome::Pass,
+        Some(expected) => Outcome::Mismatch { actual, expected },
+    }
+}
+
+// the text after `# =>` in the comment that starts at `comment_start`, with everything
+// before it blanked out so that spans into it are spans into `line`
+fn expectation(line: &str, comment_start: usize) -> Option<String> {
+    let expected = line[comment_start..]
+        .strip_prefix('#')?
+        .trim_start()
+        .strip_prefix("=>")?;
+    let offset = line.len() - expected.len();
+    Some(format!("{}{}", " ".repeat(offset), expected.trim_end()))
+}
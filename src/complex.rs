@@ -0,0 +1,245 @@
+//! Evaluation over complex numbers.
+
+use std::f64::consts::{LN_2, LN_10};
+use std::fmt;
+
+use num_complex::Complex64;
+use num_traits::Zero;
+
+use crate::ast::{BinaryOp, Expr, UnaryOp};
+use crate::eval::{Arithmetic, binary, eval_in, unary};
+use crate::{Context, Environment, EvalError, ParseError, RegisteredFunction};
+
+/// Built-in functions whose domain in `f64` grows to the complex plane,
+/// so `sqrt(-1)` or `log(-1)` have a value instead of being a domain error.
+const EXTENDED: &[&str] = &["sqrt", "log", "ln", "log10", "log2", "pow", "asin", "acos"];
+
+/// The value of an expression evaluated with [`eval_complex`].
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::{Complex, eval_complex, parse};
+///
+/// let z = eval_complex(&parse("(1 + 2*i) * (3 - i)").unwrap()).unwrap();
+/// assert_eq!(z, Complex::new(5.0, 5.0));
+/// assert_eq!(z.to_string(), "5 + 5i");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    /// The imaginary unit `i`.
+    pub const I: Complex = Complex { re: 0.0, im: 1.0 };
+
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+
+    /// Whether the imaginary part is zero.
+    pub fn is_real(&self) -> bool {
+        self.im == 0.0
+    }
+
+    /// The modulus `|z|`.
+    pub fn abs(&self) -> f64 {
+        self.re.hypot(self.im)
+    }
+}
+
+impl From<f64> for Complex {
+    fn from(re: f64) -> Self {
+        Complex { re, im: 0.0 }
+    }
+}
+
+impl From<Complex64> for Complex {
+    fn from(z: Complex64) -> Self {
+        Complex { re: z.re, im: z.im }
+    }
+}
+
+/// Prints `a + bi` or `a - bi`, with both parts even when one is zero.
+impl fmt::Display for Complex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // adding zero turns -0 into 0
+        let re = self.re + 0.0;
+        if self.im < 0.0 {
+            write!(f, "{} - {}i", re, -self.im)
+        } else {
+            write!(f, "{} + {}i", re, self.im + 0.0)
+        }
+    }
+}
+
+/// Computes `expr` over complex numbers with the constants and functions of
+/// [`Context::default`].
+///
+/// `i` is the imaginary unit unless a variable or constant of that name hides
+/// it; parse with [`ParseOptions::complex`](crate::ParseOptions::complex) to
+/// also write `4i`. Real values are computed in `f64` exactly as [`eval`](crate::eval)
+/// does, and move to the complex plane where `f64` has no value: `sqrt(-1) = i`,
+/// `log(-1) = iπ`, `(-8)^(1/3) = 1 + 1.732…i` (principal values). Arithmetic and the
+/// elementary functions (`exp`, logarithms, roots, `pow`, trigonometric and hyperbolic
+/// functions, `abs` and `sign`) accept complex arguments, as do `re`, `im`, `arg` and
+/// `conj`, which only exist here. Operations such as `%`, `!`, `floor` or `max` need
+/// real arguments and otherwise fail with [`ParseError::NotReal`], as do functions
+/// registered on a [`Context`], including those replacing a built-in name.
+pub fn eval_complex(expr: &Expr) -> Result<Complex, ParseError> {
+    Context::shared().eval_complex(expr, &Environment::new())
+}
+
+pub(crate) fn eval_complex_in(
+    expr: &Expr,
+    ctx: &Context,
+    env: &Environment,
+) -> Result<Complex, ParseError> {
+    eval_in(expr, ctx, env, &Plane).map(Complex::from)
+}
+
+/// Computes over complex numbers, in `f64` while the values are real.
+struct Plane;
+
+impl Arithmetic for Plane {
+    type Value = Complex64;
+
+    fn literal(&self, literal: &str) -> Result<Option<Complex64>, EvalError> {
+        Ok(literal.parse().ok().map(real))
+    }
+
+    fn variable(&self, value: f64) -> Result<Complex64, EvalError> {
+        Ok(real(value))
+    }
+
+    fn constant(&self, _: &str, value: f64) -> Result<Complex64, EvalError> {
+        Ok(real(value))
+    }
+
+    fn unbound(&self, name: &str) -> Option<Complex64> {
+        (name == "i").then(Complex64::i)
+    }
+
+    fn function(&self, name: &str) -> Option<fn(Complex64) -> Complex64> {
+        component(name)
+    }
+
+    fn unary(&self, op: UnaryOp, z: Complex64) -> Result<Complex64, EvalError> {
+        match op {
+            _ if z.im == 0.0 => unary(op, z.re).map(real),
+            UnaryOp::Neg => Ok(-z),
+            UnaryOp::Percent => Ok(z / 100.0),
+            UnaryOp::Factorial => Err(EvalError::NotReal),
+        }
+    }
+
+    fn binary(&self, op: BinaryOp, l: Complex64, r: Complex64) -> Result<Complex64, EvalError> {
+        if l.im == 0.0 && r.im == 0.0 {
+            match binary(op, l.re, r.re) {
+                // a negative base with a fractional exponent
+                Err(EvalError::Domain { .. }) if op == BinaryOp::Pow => {}
+                result => return result.map(real),
+            }
+        }
+        match op {
+            BinaryOp::Add => Ok(l + r),
+            BinaryOp::Sub => Ok(l - r),
+            BinaryOp::Mul => Ok(l * r),
+            BinaryOp::Div if r.is_zero() => Err(EvalError::DivisionByZero),
+            BinaryOp::Div => Ok(l / r),
+            BinaryOp::Rem | BinaryOp::FloorDiv => Err(EvalError::NotReal),
+            BinaryOp::Pow => power(l, r).ok_or(EvalError::DivisionByZero),
+        }
+    }
+
+    fn call(
+        &self,
+        name: &str,
+        function: &RegisteredFunction,
+        args: &[Complex64],
+    ) -> Result<Complex64, EvalError> {
+        if args.iter().all(|z| z.im == 0.0) {
+            let reals: Vec<f64> = args.iter().map(|z| z.re).collect();
+            match function.call(&reals) {
+                Ok(value) => return Ok(real(value)),
+                Err(EvalError::Domain { arg })
+                    if !arg.is_nan() && function.is_builtin() && EXTENDED.contains(&name) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        // a function the host registered under a built-in name only takes real arguments
+        if !function.is_builtin() {
+            return Err(EvalError::NotReal);
+        }
+        elementary(name, args).unwrap_or(Err(EvalError::NotReal))
+    }
+}
+
+fn real(x: f64) -> Complex64 {
+    Complex64::new(x, 0.0)
+}
+
+// `re`, `im`, `arg` and `conj`
+fn component(name: &str) -> Option<fn(Complex64) -> Complex64> {
+    match name {
+        "re" => Some(|z| real(z.re)),
+        "im" => Some(|z| real(z.im)),
+        "arg" => Some(|z| real(z.arg())),
+        "conj" => Some(|z| z.conj()),
+        _ => None,
+    }
+}
+
+// built-in functions of complex arguments; `None` for those only defined for real numbers
+fn elementary(name: &str, args: &[Complex64]) -> Option<Result<Complex64, EvalError>> {
+    let value = match (name, args) {
+        ("sqrt", [z]) => z.sqrt(),
+        ("cbrt", [z]) => z.cbrt(),
+        ("exp", [z]) => z.exp(),
+        ("ln" | "log", [z]) => return Some(ln(*z)),
+        ("log", [base, z]) => {
+            return Some(ln(*base).and_then(|b| {
+                if b.is_zero() {
+                    return Err(EvalError::Domain { arg: base.re });
+                }
+                Ok(ln(*z)? / b)
+            }));
+        }
+        ("log10", [z]) => return Some(ln(*z).map(|l| l / LN_10)),
+        ("log2", [z]) => return Some(ln(*z).map(|l| l / LN_2)),
+        ("pow", [z, w]) => return Some(power(*z, *w).ok_or(EvalError::DivisionByZero)),
+        ("sin", [z]) => z.sin(),
+        ("cos", [z]) => z.cos(),
+        ("tan", [z]) => z.tan(),
+        ("asin", [z]) => z.asin(),
+        ("acos", [z]) => z.acos(),
+        ("atan", [z]) => z.atan(),
+        ("sinh", [z]) => z.sinh(),
+        ("cosh", [z]) => z.cosh(),
+        ("tanh", [z]) => z.tanh(),
+        ("abs", [z]) => real(z.norm()),
+        ("sign", [z]) if z.is_zero() => *z,
+        ("sign", [z]) => z / z.norm(),
+        _ => return None,
+    };
+    Some(Ok(value))
+}
+
+fn ln(z: Complex64) -> Result<Complex64, EvalError> {
+    if z.is_zero() {
+        return Err(EvalError::Domain { arg: 0.0 });
+    }
+    Ok(z.ln())
+}
+
+// `z ^ w`, or `None` when `z` is zero and the real part of `w` is not positive
+fn power(z: Complex64, w: Complex64) -> Option<Complex64> {
+    if z.is_zero() {
+        return (w.re > 0.0).then(Complex64::zero);
+    }
+    if w.im == 0.0 && w.re.fract() == 0.0 && w.re.abs() <= i32::MAX as f64 {
+        // exact for small integer exponents, where exp(w * ln(z)) would leave rounding noise
+        return Some(z.powi(w.re as i32));
+    }
+    Some(z.powc(w))
+}
@@ -0,0 +1,177 @@
+//! Named values available to an expression during evaluation.
+
+use std::collections::HashMap;
+use std::f64::consts::{E, PI, TAU};
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+
+use crate::ast::Expr;
+use crate::complex::eval_complex_in;
+use crate::decimal::eval_decimal_in;
+use crate::eval::eval_in;
+use crate::exact::eval_exact_in;
+use crate::integer::eval_integer_in;
+use crate::{
+    Complex, Decimal, DecimalOptions, Environment, Exact, FunctionRegistry, Inexact, Integer,
+    Numeric, ParseError,
+};
+
+/// Built-in constants: name, value, and whether [`Context::strict`] keeps it.
+const CONSTANTS: &[(&str, f64, bool)] = &[
+    ("pi", PI, true),
+    ("e", E, true),
+    ("tau", TAU, true),
+    ("inf", f64::INFINITY, false),
+    ("nan", f64::NAN, false),
+];
+
+/// Everything an [`Expr`] can refer to by name.
+///
+/// [`Context::default`] knows the built-in functions and the constants `pi`,
+/// `e`, `tau`, `inf` and `nan`; [`Context::strict`] leaves out the non-finite
+/// `inf` and `nan`.
+#[derive(Debug, Clone)]
+pub struct Context {
+    constants: HashMap<String, f64>,
+    functions: FunctionRegistry,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Context::with_constants(|_| true)
+    }
+}
+
+impl Context {
+    /// A context without `inf` and `nan`, for callers that only accept finite input.
+    pub fn strict() -> Self {
+        Context::with_constants(|finite| finite)
+    }
+
+    // the default context behind the free functions such as `eval`, built once
+    pub(crate) fn shared() -> &'static Context {
+        static DEFAULT: OnceLock<Context> = OnceLock::new();
+        DEFAULT.get_or_init(Context::default)
+    }
+
+    fn with_constants(keep: impl Fn(bool) -> bool) -> Self {
+        let constants = CONSTANTS
+            .iter()
+            .filter(|(_, _, finite)| keep(*finite))
+            .map(|(name, value, _)| (name.to_string(), *value))
+            .collect();
+        Context {
+            constants,
+            functions: FunctionRegistry::default(),
+        }
+    }
+
+    /// Value of the constant `name`, if it is defined.
+    pub fn constant(&self, name: &str) -> Option<f64> {
+        self.constants.get(name).copied()
+    }
+
+    /// Whether `name` is a built-in constant that still has its built-in value.
+    pub(crate) fn is_builtin_constant(&self, name: &str) -> bool {
+        let value = self.constant(name).map(f64::to_bits);
+        CONSTANTS.iter().any(|(builtin, builtin_value, _)| {
+            *builtin == name && Some(builtin_value.to_bits()) == value
+        })
+    }
+
+    /// Defines or overrides a constant.
+    pub fn set_constant(&mut self, name: impl Into<String>, value: f64) {
+        self.constants.insert(name.into(), value);
+    }
+
+    /// Removes a constant, returning its value if it was defined.
+    pub fn remove_constant(&mut self, name: &str) -> Option<f64> {
+        self.constants.remove(name)
+    }
+
+    /// Names of all defined constants, sorted.
+    pub fn constant_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.constants.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+
+    /// Functions callable from expressions evaluated in this context.
+    pub fn functions(&self) -> &FunctionRegistry {
+        &self.functions
+    }
+
+    /// Registry to add or remove functions, e.g. `ctx.functions_mut().register(...)`.
+    pub fn functions_mut(&mut self) -> &mut FunctionRegistry {
+        &mut self.functions
+    }
+
+    /// Replaces all functions, e.g. with [`FunctionRegistry::new`] to allow none.
+    pub fn set_functions(&mut self, functions: FunctionRegistry) {
+        self.functions = functions;
+    }
+
+    /// Computes the value of `expr` with the names defined in this context.
+    pub fn eval(&self, expr: &Expr) -> Result<f64, ParseError> {
+        eval_in(expr, self, &Environment::new(), &PhantomData::<f64>)
+    }
+
+    /// Same as [`Context::eval`], with variables taken from `env`.
+    ///
+    /// A variable in `env` hides a constant of the same name.
+    pub fn eval_with(&self, expr: &Expr, env: &Environment) -> Result<f64, ParseError> {
+        eval_in(expr, self, env, &PhantomData::<f64>)
+    }
+
+    /// Same as [`eval_as`](crate::eval_as), with the names defined in this
+    /// context and variables taken from `env`.
+    pub fn eval_as<T: Numeric>(&self, expr: &Expr, env: &Environment) -> Result<T, ParseError> {
+        eval_in(expr, self, env, &PhantomData::<T>)
+    }
+
+    /// Same as [`eval_exact`](crate::eval_exact), with the names defined in this
+    /// context and variables taken from `env`.
+    ///
+    /// Variables and constants other than `pi`, `e` and `tau` are read as the
+    /// decimal they print as, so a variable set to `0.2` is exactly `1/5`.
+    /// Registered functions work on `f64`, so their results are approximations.
+    pub fn eval_exact(
+        &self,
+        expr: &Expr,
+        env: &Environment,
+        inexact: Inexact,
+    ) -> Result<Exact, ParseError> {
+        eval_exact_in(expr, self, env, inexact)
+    }
+
+    /// Same as [`eval_decimal`](crate::eval_decimal), with the names defined in
+    /// this context and variables taken from `env`.
+    ///
+    /// Variables are read as the decimal they print as; registered functions
+    /// work on `f64` and their results are rounded to `options.scale` places.
+    pub fn eval_decimal(
+        &self,
+        expr: &Expr,
+        env: &Environment,
+        options: &DecimalOptions,
+    ) -> Result<Decimal, ParseError> {
+        eval_decimal_in(expr, self, env, options)
+    }
+
+    /// Same as [`eval_integer`](crate::eval_integer), with the names defined in
+    /// this context and variables taken from `env`.
+    ///
+    /// A variable holding a whole number such as `3` is an exact integer.
+    pub fn eval_integer(&self, expr: &Expr, env: &Environment) -> Result<Integer, ParseError> {
+        eval_integer_in(expr, self, env)
+    }
+
+    /// Same as [`eval_complex`](crate::eval_complex), with the names defined in
+    /// this context and variables taken from `env`.
+    ///
+    /// Registered functions are called with real arguments only; given a
+    /// complex one they fail with [`ParseError::NotReal`].
+    pub fn eval_complex(&self, expr: &Expr, env: &Environment) -> Result<Complex, ParseError> {
+        eval_complex_in(expr, self, env)
+    }
+}
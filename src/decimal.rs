@@ -0,0 +1,168 @@
+//! Decimal evaluation at a fixed number of decimal places.
+
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Pow, Signed, ToPrimitive};
+
+use crate::ast::Expr;
+use crate::exact::eval_rounded_in;
+use crate::{Context, Environment, ParseError};
+
+/// How a result is cut down to the configured number of decimal places.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    /// To the nearest value, ties to the even last digit: `2.345 -> 2.34`, `2.355 -> 2.36`.
+    #[default]
+    HalfEven,
+    /// To the nearest value, ties away from zero: `2.345 -> 2.35`, `-2.345 -> -2.35`.
+    HalfUp,
+    /// Toward zero, dropping the extra digits: `2.349 -> 2.34`, `-2.349 -> -2.34`.
+    Down,
+}
+
+/// Precision and rounding of [`eval_decimal`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalOptions {
+    /// Number of digits kept after the decimal point.
+    pub scale: u32,
+    pub rounding: Rounding,
+}
+
+impl DecimalOptions {
+    pub fn new(scale: u32, rounding: Rounding) -> Self {
+        DecimalOptions { scale, rounding }
+    }
+
+    // `x` rounded to `scale` decimal places
+    pub(crate) fn round(&self, x: &BigRational) -> BigRational {
+        let factor = Pow::pow(BigInt::from(10), self.scale);
+        let scaled = x * &factor;
+        let whole = scaled.trunc();
+        let rest = (&scaled - &whole).abs();
+        let half = BigRational::new(BigInt::one(), BigInt::from(2));
+        let away = match self.rounding {
+            Rounding::Down => false,
+            Rounding::HalfUp => rest >= half,
+            Rounding::HalfEven => rest > half || (rest == half && whole.numer().bit(0)),
+        };
+        let whole = whole.to_integer();
+        let unscaled = if away {
+            whole + scaled.signum().to_integer()
+        } else {
+            whole
+        };
+        BigRational::new(unscaled, factor)
+    }
+
+    // the square root of `x >= 0`, correctly rounded to `scale` decimal places
+    pub(crate) fn sqrt(&self, x: &BigRational) -> BigRational {
+        let factor = Pow::pow(BigInt::from(10), self.scale);
+        // sqrt(x) * 10^scale is the square root of `scaled`; `whole` is its integer part
+        let scaled = x * Pow::pow(&factor, 2u32);
+        let whole = scaled.floor().to_integer().sqrt();
+        let midpoint = BigRational::from(&whole * &whole + &whole)
+            + BigRational::new(BigInt::one(), BigInt::from(4));
+        let up = match self.rounding {
+            Rounding::Down => false,
+            Rounding::HalfUp => scaled >= midpoint,
+            Rounding::HalfEven => scaled > midpoint || (scaled == midpoint && whole.bit(0)),
+        };
+        let unscaled = if up { whole + 1 } else { whole };
+        BigRational::new(unscaled, factor)
+    }
+}
+
+/// The value of an expression evaluated with [`eval_decimal`]: an integer
+/// count of `10^-scale` units.
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::{
+///     DecimalOptions, Rounding, eval_decimal, parse,
+/// };
+///
+/// let options = DecimalOptions::new(2, Rounding::HalfUp);
+/// let share = eval_decimal(&parse("100 / 3").unwrap(), &options).unwrap();
+/// assert_eq!(share.to_string(), "33.33");
+/// assert_eq!(share.scale(), 2);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decimal {
+    unscaled: BigInt,
+    scale: u32,
+}
+
+impl Decimal {
+    /// The digits without the decimal point, `3333` for `33.33`.
+    pub fn unscaled(&self) -> &BigInt {
+        &self.unscaled
+    }
+
+    /// Number of digits after the decimal point.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    pub fn to_rational(&self) -> BigRational {
+        BigRational::new(
+            self.unscaled.clone(),
+            Pow::pow(BigInt::from(10), self.scale),
+        )
+    }
+
+    /// The nearest `f64`, infinite when the value is out of its range.
+    pub fn to_f64(&self) -> f64 {
+        self.to_rational().to_f64().unwrap_or(f64::NAN)
+    }
+
+    // `x` rounded by `options`
+    fn new(x: &BigRational, options: &DecimalOptions) -> Self {
+        let rounded = options.round(x) * Pow::pow(BigInt::from(10), options.scale);
+        Decimal {
+            unscaled: rounded.to_integer(),
+            scale: options.scale,
+        }
+    }
+}
+
+/// Prints every digit of the scale, `12.50` or `-0.05`.
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.unscaled.abs().to_string();
+        let scale = self.scale as usize;
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        let sign = if self.unscaled.is_negative() { "-" } else { "" };
+        if frac.is_empty() {
+            write!(f, "{sign}{int}")
+        } else {
+            write!(f, "{sign}{int}.{frac}")
+        }
+    }
+}
+
+/// Computes `expr` in base 10 with the constants and functions of [`Context::default`].
+///
+/// Literals are read from their source text, so `0.1` is exactly one tenth,
+/// and are used with all their digits: `1000 * 0.005` is `5.00` at two places.
+/// The result of every operation, `/` included, is rounded to
+/// `options.scale` decimal places with `options.rounding`, the way a
+/// calculator with a fixed number of places works; `sqrt` is rounded the
+/// same way. Functions and constants without a decimal implementation,
+/// such as `ln` or `pi`, are computed in `f64` and then rounded, so they are
+/// only accurate to about 15 significant digits. A result that is infinite
+/// or `NaN` in `f64` is a [`ParseError::NotExact`].
+pub fn eval_decimal(expr: &Expr, options: &DecimalOptions) -> Result<Decimal, ParseError> {
+    Context::shared().eval_decimal(expr, &Environment::new(), options)
+}
+
+pub(crate) fn eval_decimal_in(
+    expr: &Expr,
+    ctx: &Context,
+    env: &Environment,
+    options: &DecimalOptions,
+) -> Result<Decimal, ParseError> {
+    let value = eval_rounded_in(expr, ctx, env, options)?;
+    Ok(Decimal::new(&value, options))
+}
@@ -0,0 +1,250 @@
+//! Human-readable rendering of a [`ParseError`] against its source.
+
+use std::fmt;
+
+use crate::functions::{FUNCTIONS, lookup};
+use crate::{Context, ParseError};
+
+/// A [`ParseError`] paired with the source it was produced from.
+///
+/// Rendering prints the error code and message, the offending line with the
+/// failing span underlined, and a hint when one is available:
+///
+/// ```text
+/// error[E005]: unknown function 'sqr' at 4..10
+///  --> 1:5
+///   |
+/// 1 | 2 + sqr(4)
+///   |     ^^^^^^ not a known function
+///   = hint: did you mean 'sqrt'?
+/// ```
+pub struct Diagnostic<'a> {
+    source: &'a str,
+    error: &'a ParseError,
+    origin: Option<&'a str>,
+    first_line: usize,
+    context: Option<&'a Context>,
+}
+
+impl<'a> Diagnostic<'a> {
+    pub fn new(source: &'a str, error: &'a ParseError) -> Self {
+        Diagnostic {
+            source,
+            error,
+            origin: None,
+            first_line: 1,
+            context: None,
+        }
+    }
+
+    /// Name shown before the line and column, usually a file name.
+    pub fn origin(mut self, origin: &'a str) -> Self {
+        self.origin = Some(origin);
+        self
+    }
+
+    /// Number of the first line of `source`, for sources cut out of a larger file.
+    pub fn first_line(mut self, line: usize) -> Self {
+        self.first_line = line;
+        self
+    }
+
+    /// The context the source was evaluated in, so that hints know its registered
+    /// functions and constants instead of only the built-in ones.
+    pub fn context(mut self, context: &'a Context) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// Suggestion on how to fix the error, if there is one.
+    pub fn hint(&self) -> Option<String> {
+        match self.error {
+            ParseError::UnexpectedToken {
+                found, expected, ..
+            } if !found.is_empty() && expected.iter().any(|t| t == "'*'") => {
+                Some(format!("missing an operator before '{found}'?"))
+            }
+            ParseError::UnexpectedToken { .. } => None,
+            ParseError::UnbalancedParen { .. } => {
+                if self.is_opening_bracket() {
+                    Some("add a matching ')'".to_string())
+                } else {
+                    Some("remove this ')' or add a matching '('".to_string())
+                }
+            }
+            ParseError::DivisionByZero { .. } => None,
+            ParseError::DomainError { function, .. } if function == "factorial" => {
+                Some("factorial is only defined for x >= 0".to_string())
+            }
+            ParseError::DomainError { function, .. } => {
+                // a replaced built-in has a domain of its own
+                let builtin = self.context.is_none_or(|context| {
+                    let registered = context.functions().get(function);
+                    registered.is_some_and(|f| f.is_builtin())
+                });
+                lookup(function)
+                    .filter(|_| builtin)
+                    .map(|f| format!("{} is only defined for {}", f.name, f.domain))
+            }
+            ParseError::UnknownFunction { name, .. } => {
+                let names = match self.context {
+                    Some(context) => context.functions().names(),
+                    None => FUNCTIONS.iter().map(|f| f.name).collect(),
+                };
+                Some(suggest(name, &names, "functions"))
+            }
+            ParseError::UnknownVariable { name, .. } => {
+                let default;
+                let context = match self.context {
+                    Some(context) => context,
+                    None => {
+                        default = Context::default();
+                        &default
+                    }
+                };
+                closest(name, &context.constant_names())
+                    .map(|candidate| format!("did you mean '{candidate}'?"))
+            }
+            ParseError::EmptyInput { .. } => Some("enter an expression such as 1 + 2".to_string()),
+            ParseError::InvalidNumber { .. } => {
+                Some("a number can contain at most one '.'".to_string())
+            }
+            ParseError::ArityMismatch { .. }
+            | ParseError::FunctionFailed { .. }
+            | ParseError::ExpectationFailed { .. } => None,
+            ParseError::NotExact { .. } => {
+                Some("evaluate with Inexact::Float to accept an approximation".to_string())
+            }
+            ParseError::NotReal { .. } => {
+                Some("take re(z), im(z) or abs(z) to get a real number first".to_string())
+            }
+            ParseError::TooDeep { .. } => {
+                Some("assign part of it to a variable on a line of its own".to_string())
+            }
+        }
+    }
+
+    fn is_opening_bracket(&self) -> bool {
+        self.source
+            .get(self.error.span().start..)
+            .is_some_and(|rest| rest.starts_with('('))
+    }
+
+    fn label(&self) -> String {
+        match self.error {
+            ParseError::UnexpectedToken { .. } => "unexpected here".to_string(),
+            ParseError::UnbalancedParen { .. } => {
+                if self.is_opening_bracket() {
+                    "this bracket is never closed".to_string()
+                } else {
+                    "this bracket is never opened".to_string()
+                }
+            }
+            ParseError::DivisionByZero { .. } => "this evaluates to zero".to_string(),
+            ParseError::DomainError { arg, .. } => format!("argument is {arg}"),
+            ParseError::UnknownFunction { .. } => "not a known function".to_string(),
+            ParseError::UnknownVariable { .. } => "not a variable or constant".to_string(),
+            ParseError::ArityMismatch { expected, .. } => format!("expects {expected}"),
+            ParseError::FunctionFailed { message, .. } => message.clone(),
+            ParseError::NotExact { .. } => "no exact value".to_string(),
+            ParseError::NotReal { .. } => "complex argument".to_string(),
+            ParseError::ExpectationFailed { actual, .. } => format!("evaluates to {actual}"),
+            ParseError::TooDeep { .. } => "nested too deeply".to_string(),
+            ParseError::EmptyInput { .. } => "nothing to evaluate".to_string(),
+            ParseError::InvalidNumber { .. } => "not a valid number".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let span = self.error.span();
+        let start = span.start.min(self.source.len());
+        let end = span.end.clamp(start, self.source.len());
+
+        let line_start = self.source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = self.source[start..]
+            .find('\n')
+            .map_or(self.source.len(), |i| start + i);
+        let line = self.source[line_start..line_end].trim_end_matches('\r');
+        let line_no = self.first_line + self.source[..line_start].matches('\n').count();
+
+        let prefix = &self.source[line_start..start];
+        let column = prefix.chars().count();
+        // keep tabs so the caret lines up with the printed line
+        let padding: String = prefix
+            .chars()
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let width = self.source[start..end.min(line_end)].chars().count().max(1);
+
+        let number = line_no.to_string();
+        let gutter = " ".repeat(number.len());
+
+        writeln!(f, "error[{}]: {}", self.error.code(), self.error)?;
+        match self.origin {
+            Some(origin) => writeln!(f, "{gutter}--> {origin}:{line_no}:{}", column + 1)?,
+            None => writeln!(f, "{gutter}--> {line_no}:{}", column + 1)?,
+        }
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{number} | {line}")?;
+        write!(
+            f,
+            "{gutter} | {padding}{} {}",
+            "^".repeat(width),
+            self.label()
+        )?;
+        if let Some(hint) = self.hint() {
+            write!(f, "\n{gutter} = hint: {hint}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders `error` against `source` as a multi-line diagnostic.
+///
+/// Shorthand for `Diagnostic::new(source, error).to_string()`.
+pub fn render_diagnostic(source: &str, error: &ParseError) -> String {
+    Diagnostic::new(source, error).to_string()
+}
+
+fn suggest(name: &str, candidates: &[&str], kind: &str) -> String {
+    match closest(name, candidates) {
+        Some(candidate) => format!("did you mean '{candidate}'?"),
+        None => format!("available {kind}: {}", candidates.join(", ")),
+    }
+}
+
+fn closest<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    candidates
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), *candidate))
+        .filter(|(distance, candidate)| *distance <= (name.len().max(candidate.len()) / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+// Levenshtein distance over chars, counting a swap of two neighbours as one edit
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
@@ -0,0 +1,84 @@
+//! Variables supplied by the caller at evaluation time.
+
+use std::collections::HashMap;
+
+/// Values of the variables an expression refers to, e.g. `x` or `rate`.
+///
+/// Variables are looked up before the constants of the [`Context`](crate::Context),
+/// so an `Environment` may shadow `e` or `pi`.
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::{Environment, eval_with, parse};
+///
+/// let formula = parse("price * (1 + rate)").unwrap();
+/// let env: Environment = [("price", 100.0), ("rate", 0.2)].into_iter().collect();
+/// assert_eq!(eval_with(&formula, &env).unwrap(), 120.0);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Environment {
+    variables: HashMap<String, f64>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Environment::default()
+    }
+
+    /// Sets a variable, returning its previous value.
+    pub fn set(&mut self, name: impl Into<String>, value: f64) -> Option<f64> {
+        self.variables.insert(name.into(), value)
+    }
+
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.variables.get(name).copied()
+    }
+
+    /// Removes a variable, returning its value if it was set.
+    pub fn remove(&mut self, name: &str) -> Option<f64> {
+        self.variables.remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.variables.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.variables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// Variables sorted by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, f64)> {
+        let mut entries: Vec<(&str, f64)> = self
+            .variables
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries.into_iter()
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, f64)> for Environment {
+    fn from_iter<I: IntoIterator<Item = (K, f64)>>(iter: I) -> Self {
+        Environment {
+            variables: iter.into_iter().map(|(k, v)| (k.into(), v)).collect(),
+        }
+    }
+}
+
+impl<K: Into<String>> Extend<(K, f64)> for Environment {
+    fn extend<I: IntoIterator<Item = (K, f64)>>(&mut self, iter: I) {
+        self.variables
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v)));
+    }
+}
+
+impl From<HashMap<String, f64>> for Environment {
+    fn from(variables: HashMap<String, f64>) -> Self {
+        Environment { variables }
+    }
+}
@@ -0,0 +1,233 @@
+//! Error type shared by parsing and evaluation.
+
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::Arity;
+
+/// A byte range `start..end` into the parsed source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseError {
+    /// A token the grammar does not allow at this position.
+    #[error("unexpected {} at {span}{}", describe_found(.found), describe_expected(.expected))]
+    UnexpectedToken {
+        found: String,
+        expected: Vec<String>,
+        span: Span,
+    },
+    /// A `(` without its `)` or the other way round.
+    #[error("unbalanced bracket at {span}")]
+    UnbalancedParen { span: Span },
+    /// The right operand of `/` evaluated to zero.
+    #[error("division by zero at {span}")]
+    DivisionByZero { span: Span },
+    /// A function argument outside the function's domain, e.g. `log(0)`.
+    #[error("{function} is undefined for argument {arg} at {span}")]
+    DomainError {
+        function: String,
+        arg: f64,
+        span: Span,
+    },
+    /// A call to a function that does not exist.
+    #[error("unknown function '{name}' at {span}")]
+    UnknownFunction { name: String, span: Span },
+    /// The input is empty or only whitespace.
+    #[error("empty input")]
+    EmptyInput { span: Span },
+    /// A malformed numeric literal such as `1.2.3`.
+    #[error("invalid number '{literal}' at {span}")]
+    InvalidNumber { literal: String, span: Span },
+    /// A name that is neither a variable in the environment nor a constant.
+    #[error("unknown variable '{name}' at {span}")]
+    UnknownVariable { name: String, span: Span },
+    /// A registered function failed with [`EvalError::Custom`].
+    #[error("{function} failed: {message} at {span}")]
+    FunctionFailed {
+        function: String,
+        message: String,
+        span: Span,
+    },
+    /// An operation without an exact result, such as `sqrt(2)`, in exact evaluation.
+    #[error("{operation} has no exact result at {span}")]
+    NotExact { operation: String, span: Span },
+    /// An operation only defined for real numbers, such as `floor` or `%`,
+    /// applied to a complex value in complex evaluation.
+    #[error("{operation} is only defined for real numbers at {span}")]
+    NotReal { operation: String, span: Span },
+    /// The two sides of `value == expected` evaluated to different values.
+    #[error("expected {expected}, found {actual} at {span}")]
+    ExpectationFailed {
+        actual: f64,
+        expected: f64,
+        span: Span,
+    },
+    /// Brackets, calls, signs and powers nested deeper than
+    /// [`MAX_NESTING`](crate::MAX_NESTING), or an expression tree deeper than
+    /// [`MAX_DEPTH`](crate::MAX_DEPTH), such as a chain of thousands of `+`.
+    #[error("expression nested too deeply at {span}")]
+    TooDeep { span: Span },
+    /// A function called with the wrong number of arguments.
+    #[error("{function} expects {expected}, found {found} at {span}")]
+    ArityMismatch {
+        function: String,
+        expected: Arity,
+        found: usize,
+        span: Span,
+    },
+}
+
+impl ParseError {
+    /// Stable identifier of the error kind, e.g. `E003` for division by zero.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "E001",
+            ParseError::UnbalancedParen { .. } => "E002",
+            ParseError::DivisionByZero { .. } => "E003",
+            ParseError::DomainError { .. } => "E004",
+            ParseError::UnknownFunction { .. } => "E005",
+            ParseError::EmptyInput { .. } => "E006",
+            ParseError::InvalidNumber { .. } => "E007",
+            ParseError::UnknownVariable { .. } => "E008",
+            ParseError::ArityMismatch { .. } => "E009",
+            ParseError::FunctionFailed { .. } => "E010",
+            ParseError::NotExact { .. } => "E011",
+            ParseError::NotReal { .. } => "E012",
+            ParseError::ExpectationFailed { .. } => "E013",
+            ParseError::TooDeep { .. } => "E014",
+        }
+    }
+
+    /// Name of the variant, e.g. `DivisionByZero`, for tools that report errors as data.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParseError::UnexpectedToken { .. } => "UnexpectedToken",
+            ParseError::UnbalancedParen { .. } => "UnbalancedParen",
+            ParseError::DivisionByZero { .. } => "DivisionByZero",
+            ParseError::DomainError { .. } => "DomainError",
+            ParseError::UnknownFunction { .. } => "UnknownFunction",
+            ParseError::EmptyInput { .. } => "EmptyInput",
+            ParseError::InvalidNumber { .. } => "InvalidNumber",
+            ParseError::UnknownVariable { .. } => "UnknownVariable",
+            ParseError::ArityMismatch { .. } => "ArityMismatch",
+            ParseError::FunctionFailed { .. } => "FunctionFailed",
+            ParseError::NotExact { .. } => "NotExact",
+            ParseError::NotReal { .. } => "NotReal",
+            ParseError::ExpectationFailed { .. } => "ExpectationFailed",
+            ParseError::TooDeep { .. } => "TooDeep",
+        }
+    }
+
+    /// Location of the error in the source.
+    pub fn span(&self) -> Span {
+        match self {
+            ParseError::UnexpectedToken { span, .. }
+            | ParseError::UnbalancedParen { span }
+            | ParseError::DivisionByZero { span }
+            | ParseError::DomainError { span, .. }
+            | ParseError::UnknownFunction { span, .. }
+            | ParseError::EmptyInput { span }
+            | ParseError::InvalidNumber { span, .. }
+            | ParseError::UnknownVariable { span, .. }
+            | ParseError::ArityMismatch { span, .. }
+            | ParseError::FunctionFailed { span, .. }
+            | ParseError::NotExact { span, .. }
+            | ParseError::NotReal { span, .. }
+            | ParseError::ExpectationFailed { span, .. } => *span,
+            ParseError::TooDeep { span } => *span,
+        }
+    }
+}
+
+/// Failure reported by a function body, see
+/// [`FunctionRegistry::register`](crate::FunctionRegistry::register).
+///
+/// The evaluator turns it into a [`ParseError`] carrying the function name
+/// and the span of the call.
+#[derive(Debug, Clone, PartialEq, Error)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EvalError {
+    /// `arg` is outside the function's domain.
+    #[error("argument {arg} is outside the domain")]
+    Domain { arg: f64 },
+    /// The function would divide by zero.
+    #[error("division by zero")]
+    DivisionByZero,
+    /// Any other failure, described by the message.
+    #[error("{0}")]
+    Custom(String),
+    /// No exact result, in exact and decimal evaluation; see [`ParseError::NotExact`].
+    #[error("no exact result")]
+    NotExact,
+    /// A complex argument where only a real one is accepted, in complex
+    /// evaluation; see [`ParseError::NotReal`].
+    #[error("only defined for real numbers")]
+    NotReal,
+}
+
+impl EvalError {
+    pub(crate) fn at(self, function: &str, span: Span) -> ParseError {
+        match self {
+            EvalError::Domain { arg } => ParseError::DomainError {
+                function: function.to_string(),
+                arg,
+                span,
+            },
+            EvalError::DivisionByZero => ParseError::DivisionByZero { span },
+            EvalError::Custom(message) => ParseError::FunctionFailed {
+                function: function.to_string(),
+                message,
+                span,
+            },
+            EvalError::NotExact => ParseError::NotExact {
+                operation: function.to_string(),
+                span,
+            },
+            EvalError::NotReal => ParseError::NotReal {
+                operation: function.to_string(),
+                span,
+            },
+        }
+    }
+}
+
+fn describe_found(found: &str) -> String {
+    if found.is_empty() {
+        "end of input".to_string()
+    } else {
+        format!("'{found}'")
+    }
+}
+
+fn describe_expected(expected: &[String]) -> String {
+    if expected.is_empty() {
+        String::new()
+    } else {
+        format!(", expected {}", expected.join(", "))
+    }
+}
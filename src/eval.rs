@@ -0,0 +1,296 @@
+//! Evaluation of a parsed [`Expr`].
+
+use std::marker::PhantomData;
+
+use crate::ast::{BinaryOp, Expr, UnaryOp};
+use crate::{
+    Arity, Context, Environment, EvalError, Numeric, ParseError, RegisteredFunction, Span,
+};
+
+/// Computes the value of `expr` with the constants and functions of [`Context::default`].
+///
+/// Division by zero (including `0 ^ x` for `x < 0`), unknown functions and
+/// variables, and arguments outside a function's domain (`log(x <= 0)`,
+/// `sqrt(x < 0)`, a negative base raised to a fractional power) are reported
+/// with the span of the offending node.
+pub fn eval(expr: &Expr) -> Result<f64, ParseError> {
+    eval_with(expr, &Environment::new())
+}
+
+/// Same as [`eval`], with variables taken from `env`.
+pub fn eval_with(expr: &Expr, env: &Environment) -> Result<f64, ParseError> {
+    Context::shared().eval_with(expr, env)
+}
+
+/// Computes `expr` in the number type `T`, with the constants and functions of
+/// [`Context::default`].
+///
+/// [`eval`] is `eval_as::<f64>`. Literals are read from their source text, so
+/// in `f32` the literal `0.1` is the `f32` nearest to one tenth:
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::{eval_as, parse};
+///
+/// let expr = parse("0.1 + sqrt(2)").unwrap();
+/// assert_eq!(eval_as::<f32>(&expr).unwrap(), 0.1f32 + 2f32.sqrt());
+/// assert_eq!(eval_as::<f64>(&expr).unwrap(), 0.1 + 2f64.sqrt());
+/// ```
+pub fn eval_as<T: Numeric>(expr: &Expr) -> Result<T, ParseError> {
+    Context::shared().eval_as(expr, &Environment::new())
+}
+
+/// How [`eval_in`] computes: the value of literals and names, and the result
+/// of each operation.
+///
+/// Errors carry no span; the evaluator adds it, along with the name of the
+/// operation. Every [`Numeric`] type computes through `PhantomData<T>`, exact,
+/// decimal and integer evaluation through their mode, and complex evaluation
+/// through its own implementation.
+pub(crate) trait Arithmetic {
+    type Value;
+
+    /// The value of a number literal, `None` when it is not a valid number.
+    fn literal(&self, literal: &str) -> Result<Option<Self::Value>, EvalError>;
+
+    /// The value of a variable, or of a constant the host set.
+    fn variable(&self, value: f64) -> Result<Self::Value, EvalError>;
+
+    /// The built-in constant `name`, which is `value` in `f64`.
+    fn constant(&self, name: &str, value: f64) -> Result<Self::Value, EvalError>;
+
+    /// The value of a name that is neither a variable nor a constant, such as
+    /// `i` over complex numbers.
+    fn unbound(&self, name: &str) -> Option<Self::Value> {
+        let _ = name;
+        None
+    }
+
+    /// A function of one argument that only this arithmetic has, such as `re`
+    /// over complex numbers; a function registered under the same name replaces it.
+    fn function(&self, name: &str) -> Option<fn(Self::Value) -> Self::Value> {
+        let _ = name;
+        None
+    }
+
+    fn unary(&self, op: UnaryOp, x: Self::Value) -> Result<Self::Value, EvalError>;
+
+    fn binary(
+        &self,
+        op: BinaryOp,
+        l: Self::Value,
+        r: Self::Value,
+    ) -> Result<Self::Value, EvalError>;
+
+    /// Calls `function`, registered as `name`, after its arity was checked.
+    fn call(
+        &self,
+        name: &str,
+        function: &RegisteredFunction,
+        args: &[Self::Value],
+    ) -> Result<Self::Value, EvalError>;
+}
+
+/// Computes in the [`Numeric`] type `T`.
+impl<T: Numeric> Arithmetic for PhantomData<T> {
+    type Value = T;
+
+    fn literal(&self, literal: &str) -> Result<Option<T>, EvalError> {
+        Ok(T::from_literal(literal))
+    }
+
+    fn variable(&self, value: f64) -> Result<T, EvalError> {
+        Ok(T::from_f64(value))
+    }
+
+    fn constant(&self, name: &str, value: f64) -> Result<T, EvalError> {
+        Ok(T::constant(name).unwrap_or_else(|| T::from_f64(value)))
+    }
+
+    fn unary(&self, op: UnaryOp, x: T) -> Result<T, EvalError> {
+        unary(op, x)
+    }
+
+    fn binary(&self, op: BinaryOp, l: T, r: T) -> Result<T, EvalError> {
+        binary(op, l, r)
+    }
+
+    fn call(&self, name: &str, function: &RegisteredFunction, args: &[T]) -> Result<T, EvalError> {
+        T::call(name, function, args)
+    }
+}
+
+pub(crate) fn eval_in<A: Arithmetic>(
+    expr: &Expr,
+    ctx: &Context,
+    env: &Environment,
+    arithmetic: &A,
+) -> Result<A::Value, ParseError> {
+    let eval = |expr| eval_in(expr, ctx, env, arithmetic);
+    match expr {
+        Expr::Number { literal, span, .. } => match arithmetic.literal(literal) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => Err(ParseError::InvalidNumber {
+                literal: literal.clone(),
+                span: *span,
+            }),
+            Err(e) => Err(e.at(literal, *span)),
+        },
+        Expr::Ident { name, span } => {
+            let value = match variable(ctx, env, name, *span) {
+                Ok(value) => value,
+                Err(e) => return arithmetic.unbound(name).ok_or(e),
+            };
+            // a variable or a constant the host set hides the built-in one
+            let value = if !env.contains(name) && ctx.is_builtin_constant(name) {
+                arithmetic.constant(name, value)
+            } else {
+                arithmetic.variable(value)
+            };
+            value.map_err(|e| e.at(name, *span))
+        }
+        Expr::Group { inner, .. } => eval(inner),
+        Expr::Unary { op, operand, span } => {
+            let operation = match op {
+                UnaryOp::Neg => "-",
+                UnaryOp::Percent => "percent",
+                UnaryOp::Factorial => "factorial",
+            };
+            arithmetic
+                .unary(*op, eval(operand)?)
+                .map_err(|e| e.at(operation, *span))
+        }
+        Expr::Binary { op, lhs, rhs, span } => {
+            let (left, right) = (eval(lhs)?, eval(rhs)?);
+            // a zero divisor is reported at the divisor, a zero base of a negative power at the base
+            arithmetic.binary(*op, left, right).map_err(|e| match e {
+                EvalError::DivisionByZero if *op == BinaryOp::Pow => {
+                    ParseError::DivisionByZero { span: lhs.span() }
+                }
+                EvalError::DivisionByZero => ParseError::DivisionByZero { span: rhs.span() },
+                e if *op == BinaryOp::Pow => e.at("pow", *span),
+                e => e.at(op.symbol(), *span),
+            })
+        }
+        Expr::Call { name, args, span } => {
+            // a function the host registered replaces one the arithmetic has of its own
+            let registered = ctx
+                .functions()
+                .get(name)
+                .is_some_and(|function| !function.is_builtin());
+            if let Some(own) = arithmetic.function(name).filter(|_| !registered) {
+                let [arg] = &args[..] else {
+                    return Err(ParseError::ArityMismatch {
+                        function: name.clone(),
+                        expected: Arity::Exact(1),
+                        found: args.len(),
+                        span: *span,
+                    });
+                };
+                return Ok(own(eval(arg)?));
+            }
+            let function = function(ctx, name, args.len(), *span)?;
+            let values = args.iter().map(eval).collect::<Result<Vec<_>, _>>()?;
+            arithmetic
+                .call(name, function, &values)
+                .map_err(|e| e.at(name, *span))
+        }
+    }
+}
+
+// variables hide constants with the same name
+fn variable(ctx: &Context, env: &Environment, name: &str, span: Span) -> Result<f64, ParseError> {
+    env.get(name)
+        .or_else(|| ctx.constant(name))
+        .ok_or_else(|| ParseError::UnknownVariable {
+            name: name.to_string(),
+            span,
+        })
+}
+
+// the function called `name`, if it takes `count` arguments
+fn function<'a>(
+    ctx: &'a Context,
+    name: &str,
+    count: usize,
+    span: Span,
+) -> Result<&'a RegisteredFunction, ParseError> {
+    let Some(function) = ctx.functions().get(name) else {
+        return Err(ParseError::UnknownFunction {
+            name: name.to_string(),
+            span,
+        });
+    };
+    if !function.arity().accepts(count) {
+        return Err(ParseError::ArityMismatch {
+            function: name.to_string(),
+            expected: function.arity(),
+            found: count,
+            span,
+        });
+    }
+    Ok(function)
+}
+
+pub(crate) fn unary<T: Numeric>(op: UnaryOp, x: T) -> Result<T, EvalError> {
+    match op {
+        UnaryOp::Neg => Ok(x.neg()),
+        UnaryOp::Percent => x.div(&T::from_f64(100.0)),
+        UnaryOp::Factorial => x.factorial(),
+    }
+}
+
+pub(crate) fn binary<T: Numeric>(op: BinaryOp, l: T, r: T) -> Result<T, EvalError> {
+    match op {
+        BinaryOp::Add => Ok(l.add(&r)),
+        BinaryOp::Sub => Ok(l.sub(&r)),
+        BinaryOp::Mul => Ok(l.mul(&r)),
+        BinaryOp::Div => l.div(&r),
+        BinaryOp::Rem => l.rem(&r),
+        BinaryOp::FloorDiv => l.floor_div(&r),
+        BinaryOp::Pow => l.pow(&r),
+    }
+}
+
+pub(crate) fn factorial(x: f64) -> Option<f64> {
+    if x < 0.0 || x.is_nan() {
+        return None;
+    }
+    if x.fract() == 0.0 {
+        // exact up to 170!, past that the product overflows to infinity anyway
+        return Some((1..=x.min(171.0) as u32).fold(1.0, |acc, k| acc * k as f64));
+    }
+    Some(gamma(x + 1.0))
+}
+
+// Lanczos approximation (g = 7, n = 9), accurate to about 15 digits for x >= 0.5
+fn gamma(x: f64) -> f64 {
+    const G: f64 = 7.0;
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    let x = x - 1.0;
+    let t = x + G + 0.5;
+    let sum = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |acc, (i, c)| {
+            acc + c / (x + i as f64 + 1.0)
+        });
+    let scale = (2.0 * std::f64::consts::PI).sqrt() * sum;
+    let direct = scale * t.powf(x + 0.5) * (-t).exp();
+    if direct.is_finite() {
+        return direct;
+    }
+    // past about 140! `t^(x + 0.5)` overflows and `e^-t` underflows even where the
+    // result still fits, so the product is formed as one exponent instead
+    ((x + 0.5) * t.ln() - t + scale.ln()).exp()
+}
@@ -0,0 +1,385 @@
+//! Exact evaluation over arbitrary-precision rationals.
+
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Pow, Signed, ToPrimitive, Zero};
+
+use crate::ast::{BinaryOp, Expr, UnaryOp};
+use crate::decimal::DecimalOptions;
+use crate::eval::{Arithmetic, binary, eval_in, unary};
+use crate::{Context, Environment, EvalError, ParseError, RegisteredFunction};
+
+/// Largest size in bits, of numerator or denominator, of an exact `x ^ y`; past it the
+/// power is approximated, since the result would take too long to compute and too
+/// much memory to hold.
+const MAX_POWER_BITS: u64 = 1 << 20;
+/// Largest `n` for which `n!` is computed exactly.
+const MAX_FACTORIAL: u64 = 10_000;
+
+/// What exact evaluation does with an operation that has no exact result,
+/// such as `sqrt(2)`, `log(3)` or the constant `pi`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Inexact {
+    /// Continue in `f64` and return an [`Exact::Float`].
+    #[default]
+    Float,
+    /// Fail with [`ParseError::NotExact`].
+    Error,
+}
+
+/// The value of an expression evaluated with [`eval_exact`].
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::{Inexact, eval_exact, parse};
+///
+/// let third = eval_exact(&parse("1/3").unwrap(), Inexact::Error).unwrap();
+/// assert_eq!(third.to_string(), "1/3");
+///
+/// let sum = eval_exact(&parse("0.1 + 0.2").unwrap(), Inexact::Error).unwrap();
+/// assert_eq!(sum.to_string(), "3/10");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Exact {
+    /// An exact value such as `1/3`.
+    Rational(BigRational),
+    /// An approximation, once an operation without an exact result was applied.
+    Float(f64),
+}
+
+impl Exact {
+    /// Whether the value is exact rather than an approximation.
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Exact::Rational(_))
+    }
+
+    /// The exact value, if there is one.
+    pub fn as_rational(&self) -> Option<&BigRational> {
+        match self {
+            Exact::Rational(r) => Some(r),
+            Exact::Float(_) => None,
+        }
+    }
+
+    /// The nearest `f64`, infinite when the value is out of its range.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Exact::Rational(r) => r.to_f64().unwrap_or(f64::NAN),
+            Exact::Float(x) => *x,
+        }
+    }
+}
+
+/// Prints `3`, `-1/3`, or the `f64` of an approximation.
+impl fmt::Display for Exact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exact::Rational(r) if r.is_integer() => write!(f, "{}", r.numer()),
+            Exact::Rational(r) => write!(f, "{}/{}", r.numer(), r.denom()),
+            Exact::Float(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+/// Computes `expr` exactly with the constants and functions of [`Context::default`].
+///
+/// Literals are read from their source text, so `0.1` is exactly `1/10`.
+/// `+`, `-`, `*`, `/`, `%`, `//`, integer powers, `!` of integers and the
+/// functions `abs`, `sign`, `floor`, `ceil`, `round`, `trunc`, `min`, `max`
+/// and `clamp` stay exact, as do `sqrt`, `cbrt` and fractional powers whose
+/// result is rational (`sqrt(9/4) = 3/2`). Everything else is handled
+/// according to `inexact`.
+pub fn eval_exact(expr: &Expr, inexact: Inexact) -> Result<Exact, ParseError> {
+    Context::shared().eval_exact(expr, &Environment::new(), inexact)
+}
+
+pub(crate) fn eval_exact_in(
+    expr: &Expr,
+    ctx: &Context,
+    env: &Environment,
+    inexact: Inexact,
+) -> Result<Exact, ParseError> {
+    eval_in(expr, ctx, env, &Mode::Exact(inexact))
+}
+
+// the value of `expr` with every intermediate result rounded by `options`
+pub(crate) fn eval_rounded_in(
+    expr: &Expr,
+    ctx: &Context,
+    env: &Environment,
+    options: &DecimalOptions,
+) -> Result<BigRational, ParseError> {
+    match eval_in(expr, ctx, env, &Mode::Decimal(options))? {
+        // a literal or variable on its own is not rounded yet
+        Exact::Rational(r) => Ok(options.round(&r)),
+        // approximations are rounded to a rational as soon as they are made
+        Exact::Float(_) => unreachable!("decimal evaluation produced a float"),
+    }
+}
+
+// the value of `expr`, a rational only while every intermediate result is an integer
+pub(crate) fn eval_whole_in(
+    expr: &Expr,
+    ctx: &Context,
+    env: &Environment,
+) -> Result<Exact, ParseError> {
+    eval_in(expr, ctx, env, &Mode::Integer)
+}
+
+/// Which values are kept as rationals.
+#[derive(Clone, Copy)]
+enum Mode<'a> {
+    /// Every exact result, with the rest handled by [`Inexact`].
+    Exact(Inexact),
+    /// Every result, rounded to a fixed number of decimal places.
+    Decimal(&'a DecimalOptions),
+    /// Integers; `/` and any fraction continue in `f64`.
+    Integer,
+}
+
+impl Arithmetic for Mode<'_> {
+    type Value = Exact;
+
+    fn literal(&self, literal: &str) -> Result<Option<Exact>, EvalError> {
+        match decimal(literal) {
+            Some(r) => Ok(Some(self.operand(r))),
+            // an exponent too large to expand, such as `1e999999`
+            None => match literal.parse::<f64>() {
+                Ok(value) => self.approximate(|| Ok(value)).map(Some),
+                Err(_) => Ok(None),
+            },
+        }
+    }
+
+    fn variable(&self, value: f64) -> Result<Exact, EvalError> {
+        match decimal(&value.to_string()) {
+            Some(r) => Ok(self.operand(r)),
+            None => self.approximate(|| Ok(value)),
+        }
+    }
+
+    // none of the built-in constants is rational
+    fn constant(&self, _: &str, value: f64) -> Result<Exact, EvalError> {
+        self.approximate(|| Ok(value))
+    }
+
+    fn unary(&self, op: UnaryOp, x: Exact) -> Result<Exact, EvalError> {
+        let x = match x {
+            Exact::Rational(x) => x,
+            Exact::Float(x) => return unary(op, x).map(Exact::Float),
+        };
+        match op {
+            UnaryOp::Neg => Ok(Exact::Rational(-x)),
+            UnaryOp::Percent => Ok(self.rational(x / BigInt::from(100))),
+            UnaryOp::Factorial => {
+                if x.is_negative() {
+                    return Err(EvalError::Domain {
+                        arg: x.to_f64().unwrap_or(f64::NAN),
+                    });
+                }
+                match x.to_integer().to_u64() {
+                    Some(n) if x.is_integer() && n <= MAX_FACTORIAL => {
+                        let product = (2..=n).fold(BigInt::one(), |acc, k| acc * k);
+                        Ok(Exact::Rational(product.into()))
+                    }
+                    _ => {
+                        let value = x.to_f64().unwrap_or(f64::INFINITY);
+                        self.approximate(|| unary(op, value))
+                    }
+                }
+            }
+        }
+    }
+
+    fn binary(&self, op: BinaryOp, l: Exact, r: Exact) -> Result<Exact, EvalError> {
+        let (l, r) = match (l, r) {
+            (Exact::Rational(l), Exact::Rational(r)) => (l, r),
+            (l, r) => return binary(op, l.to_f64(), r.to_f64()).map(Exact::Float),
+        };
+        if let (Mode::Integer, BinaryOp::Div) = (self, op) {
+            let (l, r) = (
+                l.to_f64().unwrap_or(f64::NAN),
+                r.to_f64().unwrap_or(f64::NAN),
+            );
+            return binary(op, l, r).map(Exact::Float);
+        }
+        let exact = match op {
+            BinaryOp::Add => l + r,
+            BinaryOp::Sub => l - r,
+            BinaryOp::Mul => l * r,
+            BinaryOp::Div | BinaryOp::Rem | BinaryOp::FloorDiv => {
+                if r.is_zero() {
+                    return Err(EvalError::DivisionByZero);
+                }
+                let quotient = &l / &r;
+                match op {
+                    BinaryOp::Rem => l - r * quotient.trunc(),
+                    BinaryOp::FloorDiv => quotient.floor(),
+                    _ => quotient,
+                }
+            }
+            BinaryOp::Pow => match power(&l, &r)? {
+                Some(result) => result,
+                None => {
+                    let (l, r) = (
+                        l.to_f64().unwrap_or(f64::NAN),
+                        r.to_f64().unwrap_or(f64::NAN),
+                    );
+                    return self.approximate(|| binary(op, l, r));
+                }
+            },
+        };
+        Ok(self.rational(exact))
+    }
+
+    fn call(
+        &self,
+        name: &str,
+        function: &RegisteredFunction,
+        args: &[Exact],
+    ) -> Result<Exact, EvalError> {
+        let rationals: Option<Vec<&BigRational>> = args.iter().map(Exact::as_rational).collect();
+        // a built-in replaced through the registry is only known as an f64 function
+        if let Some(args) = rationals.filter(|_| function.is_builtin()) {
+            if let Some(result) = call(name, &args)? {
+                return Ok(self.rational(result));
+            }
+            if let (Mode::Decimal(options), "sqrt", [x]) = (self, name, &args[..]) {
+                return Ok(Exact::Rational(options.sqrt(x)));
+            }
+        }
+        let floats: Vec<f64> = args.iter().map(Exact::to_f64).collect();
+        self.approximate(|| function.call(&floats))
+    }
+}
+
+impl Mode<'_> {
+    // a literal or variable, which decimal mode keeps unrounded so that `1000 * 0.005`
+    // is 5.00 at two decimal places; only results are rounded
+    fn operand(&self, x: BigRational) -> Exact {
+        match self {
+            Mode::Integer => self.rational(x),
+            Mode::Exact(_) | Mode::Decimal(_) => Exact::Rational(x),
+        }
+    }
+
+    fn rational(&self, x: BigRational) -> Exact {
+        match self {
+            Mode::Decimal(options) => Exact::Rational(options.round(&x)),
+            Mode::Integer if !x.is_integer() => Exact::Float(x.to_f64().unwrap_or(f64::NAN)),
+            Mode::Exact(_) | Mode::Integer => Exact::Rational(x),
+        }
+    }
+
+    fn approximate(
+        &self,
+        value: impl FnOnce() -> Result<f64, EvalError>,
+    ) -> Result<Exact, EvalError> {
+        match self {
+            Mode::Decimal(options) => match decimal(&value()?.to_string()) {
+                Some(x) => Ok(Exact::Rational(options.round(&x))),
+                None => Err(EvalError::NotExact),
+            },
+            Mode::Exact(Inexact::Float) | Mode::Integer => value().map(Exact::Float),
+            Mode::Exact(Inexact::Error) => Err(EvalError::NotExact),
+        }
+    }
+}
+
+// built-in functions with an exact result for rational arguments; `None` when there is none
+fn call(name: &str, args: &[&BigRational]) -> Result<Option<BigRational>, EvalError> {
+    let domain = |arg: &BigRational| EvalError::Domain {
+        arg: arg.to_f64().unwrap_or(f64::NAN),
+    };
+    let result = match (name, args) {
+        ("abs", [x]) => x.abs(),
+        ("sign", [x]) => x.signum(),
+        ("floor", [x]) => x.floor(),
+        ("ceil", [x]) => x.ceil(),
+        ("round", [x]) => x.round(),
+        ("trunc", [x]) => x.trunc(),
+        ("min", _) => args.iter().copied().min().cloned().unwrap_or_default(),
+        ("max", _) => args.iter().copied().max().cloned().unwrap_or_default(),
+        ("clamp", [x, lo, hi]) => {
+            if lo > hi {
+                return Err(domain(lo));
+            }
+            (*x).clone().clamp((*lo).clone(), (*hi).clone())
+        }
+        ("sqrt", [x]) if x.is_negative() => return Err(domain(x)),
+        ("sqrt", [x]) => return Ok(root(x, 2)),
+        ("cbrt", [x]) => return Ok(root(x, 3)),
+        ("pow", [x, y]) => return power(x, y),
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+// `x ^ y`, or `None` when the result is irrational or too large to compute
+fn power(x: &BigRational, y: &BigRational) -> Result<Option<BigRational>, EvalError> {
+    if x.is_zero() && y.is_negative() {
+        return Err(EvalError::DivisionByZero);
+    }
+    if x.is_negative() && !y.is_integer() {
+        return Err(EvalError::Domain {
+            arg: x.to_f64().unwrap_or(f64::NAN),
+        });
+    }
+    let (Some(p), Some(q)) = (y.numer().abs().to_u64(), y.denom().to_u32()) else {
+        return Ok(None);
+    };
+    let Some(base) = root(x, q) else {
+        return Ok(None);
+    };
+    let bits = base.numer().bits().max(base.denom().bits());
+    if bits > 1 && p.checked_mul(bits).is_none_or(|size| size > MAX_POWER_BITS) {
+        return Ok(None);
+    }
+    let result = Pow::pow(base, p);
+    Ok(Some(if y.is_negative() {
+        result.recip()
+    } else {
+        result
+    }))
+}
+
+// the `n`-th root of `x`, if it is rational
+fn root(x: &BigRational, n: u32) -> Option<BigRational> {
+    if x.is_negative() {
+        return if n % 2 == 1 {
+            root(&-x, n).map(|r| -r)
+        } else {
+            None
+        };
+    }
+    let numer = x.numer().nth_root(n);
+    let denom = x.denom().nth_root(n);
+    let exact = Pow::pow(&numer, n) == *x.numer() && Pow::pow(&denom, n) == *x.denom();
+    exact.then(|| BigRational::new(numer, denom))
+}
+
+// `12.5`, `.5` or `-3.` as a fraction of integers
+fn decimal(text: &str) -> Option<BigRational> {
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (text, 0),
+    };
+    // `10^n` has fewer than `4n` bits
+    if exponent.unsigned_abs() > MAX_POWER_BITS / 4 {
+        return None;
+    }
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{int}{frac}");
+    if digits.is_empty() || digits == "-" {
+        return None;
+    }
+    let numer: BigInt = digits.parse().ok()?;
+    let shift = exponent - frac.len() as i64;
+    let scale = Pow::pow(BigInt::from(10), shift.unsigned_abs());
+    Some(if shift < 0 {
+        BigRational::new(numer, scale)
+    } else {
+        BigRational::from_integer(numer * scale)
+    })
+}
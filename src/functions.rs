@@ -0,0 +1,422 @@
+//! Built-in functions callable from expressions.
+
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_2, PI};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::EvalError;
+
+/// How many arguments a function accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Arity {
+    /// Exactly this many.
+    Exact(usize),
+    /// Between the two bounds, inclusive.
+    Range(usize, usize),
+    /// This many or more.
+    AtLeast(usize),
+}
+
+impl Arity {
+    pub fn accepts(self, count: usize) -> bool {
+        match self {
+            Arity::Exact(n) => count == n,
+            Arity::Range(min, max) => (min..=max).contains(&count),
+            Arity::AtLeast(min) => count >= min,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (text, last) = match *self {
+            Arity::Exact(n) => (n.to_string(), n),
+            Arity::Range(min, max) if max == min + 1 => (format!("{min} or {max}"), max),
+            Arity::Range(min, max) => (format!("{min} to {max}"), max),
+            Arity::AtLeast(min) => (format!("at least {min}"), min),
+        };
+        let noun = if last == 1 { "argument" } else { "arguments" };
+        write!(f, "{text} {noun}")
+    }
+}
+
+/// A built-in function.
+pub(crate) struct Function {
+    pub name: &'static str,
+    pub arity: Arity,
+    /// Human-readable domain, used in diagnostics.
+    pub domain: &'static str,
+    /// Only called with a number of arguments accepted by `arity`.
+    pub apply: fn(&[f64]) -> Result<f64, EvalError>,
+}
+
+const ANY: &str = "any x";
+const ONE: Arity = Arity::Exact(1);
+const TWO: Arity = Arity::Exact(2);
+
+pub(crate) const FUNCTIONS: &[Function] = &[
+    // logarithms and powers
+    Function {
+        name: "log",
+        arity: Arity::Range(1, 2),
+        domain: "x > 0, and base > 0, base != 1 in log(base, x)",
+        apply: handle_log,
+    },
+    Function {
+        name: "ln",
+        arity: ONE,
+        domain: "x > 0",
+        apply: |a| positive(a[0]).map(f64::ln),
+    },
+    Function {
+        name: "log10",
+        arity: ONE,
+        domain: "x > 0",
+        apply: |a| positive(a[0]).map(f64::log10),
+    },
+    Function {
+        name: "log2",
+        arity: ONE,
+        domain: "x > 0",
+        apply: |a| positive(a[0]).map(f64::log2),
+    },
+    Function {
+        name: "exp",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].exp()),
+    },
+    Function {
+        name: "pow",
+        arity: TWO,
+        domain: "x >= 0, or an integer y for x < 0",
+        apply: |a| power(a[0], a[1]),
+    },
+    Function {
+        name: "sqrt",
+        arity: ONE,
+        domain: "x >= 0",
+        apply: |a| non_negative(a[0]).map(f64::sqrt),
+    },
+    Function {
+        name: "cbrt",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].cbrt()),
+    },
+    Function {
+        name: "hypot",
+        arity: TWO,
+        domain: ANY,
+        apply: |a| Ok(a[0].hypot(a[1])),
+    },
+    // trigonometry
+    Function {
+        name: "sin",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].sin()),
+    },
+    Function {
+        name: "cos",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].cos()),
+    },
+    Function {
+        name: "tan",
+        arity: ONE,
+        domain: "x != pi/2 + k*pi",
+        apply: |a| handle_tan(a[0]),
+    },
+    Function {
+        name: "asin",
+        arity: ONE,
+        domain: "-1 <= x <= 1",
+        apply: |a| unit_interval(a[0]).map(f64::asin),
+    },
+    Function {
+        name: "acos",
+        arity: ONE,
+        domain: "-1 <= x <= 1",
+        apply: |a| unit_interval(a[0]).map(f64::acos),
+    },
+    Function {
+        name: "atan",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].atan()),
+    },
+    Function {
+        name: "atan2",
+        arity: TWO,
+        domain: ANY,
+        apply: |a| Ok(a[0].atan2(a[1])),
+    },
+    Function {
+        name: "sinh",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].sinh()),
+    },
+    Function {
+        name: "cosh",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].cosh()),
+    },
+    Function {
+        name: "tanh",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].tanh()),
+    },
+    // rounding, sign and comparison
+    Function {
+        name: "abs",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].abs()),
+    },
+    Function {
+        name: "floor",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].floor()),
+    },
+    Function {
+        name: "ceil",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].ceil()),
+    },
+    Function {
+        name: "round",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].round()),
+    },
+    Function {
+        name: "trunc",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(a[0].trunc()),
+    },
+    Function {
+        name: "sign",
+        arity: ONE,
+        domain: ANY,
+        apply: |a| Ok(handle_sign(a[0])),
+    },
+    Function {
+        name: "min",
+        arity: Arity::AtLeast(1),
+        domain: ANY,
+        apply: |a| Ok(a.iter().copied().fold(f64::INFINITY, f64::min)),
+    },
+    Function {
+        name: "max",
+        arity: Arity::AtLeast(1),
+        domain: ANY,
+        apply: |a| Ok(a.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+    },
+    Function {
+        name: "clamp",
+        arity: Arity::Exact(3),
+        domain: "min <= max in clamp(x, min, max)",
+        apply: |a| {
+            if a[1] > a[2] {
+                return Err(EvalError::Domain { arg: a[1] });
+            }
+            Ok(a[0].clamp(a[1], a[2]))
+        },
+    },
+];
+
+pub(crate) fn lookup(name: &str) -> Option<&'static Function> {
+    FUNCTIONS.iter().find(|f| f.name == name)
+}
+
+/// `x` raised to `y`, shared by the `^` operator and `pow(x, y)`.
+pub(crate) fn power(x: f64, y: f64) -> Result<f64, EvalError> {
+    if x == 0.0 && y < 0.0 {
+        return Err(EvalError::DivisionByZero);
+    }
+    if x < 0.0 && y.fract() != 0.0 {
+        return Err(EvalError::Domain { arg: x });
+    }
+    Ok(x.powf(y))
+}
+
+fn positive(x: f64) -> Result<f64, EvalError> {
+    if x > 0.0 {
+        Ok(x)
+    } else {
+        Err(EvalError::Domain { arg: x })
+    }
+}
+
+fn non_negative(x: f64) -> Result<f64, EvalError> {
+    if x >= 0.0 {
+        Ok(x)
+    } else {
+        Err(EvalError::Domain { arg: x })
+    }
+}
+
+fn unit_interval(x: f64) -> Result<f64, EvalError> {
+    if (-1.0..=1.0).contains(&x) {
+        Ok(x)
+    } else {
+        Err(EvalError::Domain { arg: x })
+    }
+}
+
+// log(x) is the natural logarithm, log(base, x) the logarithm in `base`
+fn handle_log(a: &[f64]) -> Result<f64, EvalError> {
+    match *a {
+        [x] => positive(x).map(f64::ln),
+        [base, x] => {
+            if base == 1.0 {
+                return Err(EvalError::Domain { arg: base });
+            }
+            Ok(positive(x)?.ln() / positive(base)?.ln())
+        }
+        _ => unreachable!("arity is checked before the call"),
+    }
+}
+
+// tan has poles at pi/2 + k*pi; the argument rarely hits them exactly in
+// floating point, so anything within rounding distance counts as a pole
+fn handle_tan(x: f64) -> Result<f64, EvalError> {
+    let k = ((x - FRAC_PI_2) / PI).round();
+    let pole = FRAC_PI_2 + k * PI;
+    if (x - pole).abs() <= 4.0 * f64::EPSILON * pole.abs().max(1.0) {
+        return Err(EvalError::Domain { arg: x });
+    }
+    Ok(x.tan())
+}
+
+// unlike f64::signum, the sign of zero is zero
+fn handle_sign(x: f64) -> f64 {
+    if x == 0.0 { 0.0 } else { x.signum() }
+}
+
+type Body = dyn Fn(&[f64]) -> Result<f64, EvalError> + Send + Sync;
+
+/// A function known to a [`FunctionRegistry`].
+#[derive(Clone)]
+pub struct RegisteredFunction {
+    arity: Arity,
+    body: Arc<Body>,
+    /// Whether this is one of the built-in functions rather than a replacement.
+    builtin: bool,
+}
+
+impl RegisteredFunction {
+    pub fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    /// Whether this is the built-in function of its name, as opposed to one
+    /// added with [`FunctionRegistry::register`].
+    pub fn is_builtin(&self) -> bool {
+        self.builtin
+    }
+
+    /// Calls the function. The caller is expected to check the arity first.
+    pub fn call(&self, args: &[f64]) -> Result<f64, EvalError> {
+        (self.body)(args)
+    }
+}
+
+/// Named functions available to expressions, see [`Context::functions_mut`](crate::Context::functions_mut).
+///
+/// [`FunctionRegistry::default`] holds the built-in functions (`sqrt`, `log`, `sin`, ...);
+/// [`FunctionRegistry::new`] starts empty.
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::{Arity, Context, EvalError, parse};
+///
+/// let mut ctx = Context::default();
+/// ctx.functions_mut().register("tax", Arity::Exact(1), |args| {
+///     if args[0] < 0.0 {
+///         return Err(EvalError::Domain { arg: args[0] });
+///     }
+///     Ok(args[0] * 0.2)
+/// });
+/// assert_eq!(ctx.eval(&parse("100 + tax(100)").unwrap()).unwrap(), 120.0);
+/// ```
+#[derive(Clone)]
+pub struct FunctionRegistry {
+    functions: HashMap<String, RegisteredFunction>,
+}
+
+impl FunctionRegistry {
+    /// A registry without any functions.
+    pub fn new() -> Self {
+        FunctionRegistry {
+            functions: HashMap::new(),
+        }
+    }
+
+    /// Adds a function, replacing any previous one with the same name.
+    ///
+    /// `body` is only called with a number of arguments accepted by `arity`.
+    pub fn register<F>(&mut self, name: impl Into<String>, arity: Arity, body: F)
+    where
+        F: Fn(&[f64]) -> Result<f64, EvalError> + Send + Sync + 'static,
+    {
+        let function = RegisteredFunction {
+            arity,
+            body: Arc::new(body),
+            builtin: false,
+        };
+        self.functions.insert(name.into(), function);
+    }
+
+    /// Removes a function, returning `true` if it was registered.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.functions.remove(name).is_some()
+    }
+
+    pub fn get(&self, name: &str) -> Option<&RegisteredFunction> {
+        self.functions.get(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Names of all registered functions, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.functions.keys().map(String::as_str).collect();
+        names.sort();
+        names
+    }
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        let mut registry = FunctionRegistry::new();
+        for function in FUNCTIONS {
+            let builtin = RegisteredFunction {
+                arity: function.arity,
+                body: Arc::new(function.apply),
+                builtin: true,
+            };
+            registry
+                .functions
+                .insert(function.name.to_string(), builtin);
+        }
+        registry
+    }
+}
+
+impl fmt::Debug for FunctionRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.names()).finish()
+    }
+}
@@ -0,0 +1,87 @@
+//! Integer evaluation without overflow.
+
+use std::fmt;
+
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::ast::Expr;
+use crate::exact::{Exact, eval_whole_in};
+use crate::{Context, Environment, ParseError};
+
+/// The value of an expression evaluated with [`eval_integer`].
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::{Integer, eval_integer, parse};
+///
+/// let big = eval_integer(&parse("2^100").unwrap()).unwrap();
+/// assert_eq!(big.to_string(), "1267650600228229401496703205376");
+/// assert!(big.is_exact());
+///
+/// let half = eval_integer(&parse("1 / 2").unwrap()).unwrap();
+/// assert_eq!(half, Integer::Approximate(0.5));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Integer {
+    /// An exact integer of any size.
+    Exact(BigInt),
+    /// An `f64`, once a division or a non-integer was involved.
+    Approximate(f64),
+}
+
+impl Integer {
+    /// Whether the value is an exact integer rather than an approximation.
+    pub fn is_exact(&self) -> bool {
+        matches!(self, Integer::Exact(_))
+    }
+
+    /// The exact integer, if there is one.
+    pub fn as_bigint(&self) -> Option<&BigInt> {
+        match self {
+            Integer::Exact(n) => Some(n),
+            Integer::Approximate(_) => None,
+        }
+    }
+
+    /// The nearest `f64`, infinite when the value is out of its range.
+    pub fn to_f64(&self) -> f64 {
+        match self {
+            Integer::Exact(n) => n.to_f64().unwrap_or(f64::NAN),
+            Integer::Approximate(x) => *x,
+        }
+    }
+}
+
+/// Prints every digit of an exact integer, or the `f64` of an approximation.
+impl fmt::Display for Integer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Integer::Exact(n) => write!(f, "{n}"),
+            Integer::Approximate(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+/// Computes `expr` with unbounded integers while it only involves integers,
+/// using the constants and functions of [`Context::default`].
+///
+/// `+`, `-`, `*`, `%`, `//`, `^` with a non-negative exponent, `!` and
+/// functions such as `abs`, `min` or `max` keep integers exact, so `2^200`
+/// and `30!` have every digit. A non-integer literal, `/`, an irrational
+/// constant or any result that is not an integer continues in `f64` and makes
+/// the result an [`Integer::Approximate`].
+pub fn eval_integer(expr: &Expr) -> Result<Integer, ParseError> {
+    Context::shared().eval_integer(expr, &Environment::new())
+}
+
+pub(crate) fn eval_integer_in(
+    expr: &Expr,
+    ctx: &Context,
+    env: &Environment,
+) -> Result<Integer, ParseError> {
+    Ok(match eval_whole_in(expr, ctx, env)? {
+        // integer evaluation keeps only integers as rationals
+        Exact::Rational(r) => Integer::Exact(r.to_integer()),
+        Exact::Float(x) => Integer::Approximate(x),
+    })
+}
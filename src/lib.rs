@@ -6,21 +6,59 @@
 //! ## Supported Features
 //!
 //! - **Basic arithmetic operations:** `+`, `-`, `*`, `/`  
+//! - **Remainder and floor division:** `7 % 3 = 1`, `7 // 2 = 3`, at the same level as `*` and `/`  
+//! - **Factorial:** postfix `5! = 120`, extended to non-integers with the gamma function (`0.5!`);
+//!   negative arguments are a domain error  
+//! - **Percent:** postfix `50% = 0.5` when parsing with [`ParseOptions::percent`]; in that mode
+//!   `%` is not available as the remainder operator  
+//! - **Exponentiation:** `^` (or `**`), right-associative and binding tighter than unary minus,
+//!   so `2^3^2 = 512` and `-2^2 = -4`  
 //! - **Nested brackets:** `((...))` for grouping expressions  
 //! - **Negative numbers:** `-x` or expressions like `-(1 + 2)`  
+//! - **Scientific notation:** `1e5`, `2.5E-3`; `2*e` is still the constant `e`  
 //! - **Special functions:**
 //!   - `log(x)` — computes the **natural logarithm (ln)**  
 //!   - `sqrt(x)` — computes the **square root**
+//!   - logarithms and powers: `ln`, `log10`, `log2`, `exp`, `cbrt`  
+//!   - trigonometry: `sin`, `cos`, `tan`, `asin`, `acos`, `atan`, `sinh`, `cosh`, `tanh`  
+//!   - rounding and sign: `abs`, `floor`, `ceil`, `round`, `trunc`, `sign`  
+//!   - several arguments: `min(...)`, `max(...)`, `atan2(y, x)`, `hypot(x, y)`, `pow(x, y)`,
+//!     `log(base, x)`, `clamp(x, min, max)`; a wrong number of arguments is an `ArityMismatch`  
+//!   - each function checks its domain: `log(x <= 0)`, `sqrt(x < 0)`, `asin(2)` or
+//!     `tan(pi / 2)` are errors, not `NaN` or infinity
+//! - **Constants:** `pi`, `e`, `tau`, `inf` and `nan`, e.g. `2*pi`, `e^2`, `log(e)`;
+//!   [`Context::strict`] leaves out `inf` and `nan`  
+//! - **Variables:** any other name, e.g. `x`, `rate` or `total_2024`, taken from an
+//!   [`Environment`] at evaluation time  
+//! - **Assignments:** `rate = 0.2` stores a variable for the following lines of a file or
+//!   [`Session`]; `ans` (or `_`) is the result of the previous line  
+//! - **Exact arithmetic:** [`eval_exact`] computes with arbitrary-precision rationals, so
+//!   `0.1 + 0.2 = 3/10` and `1/3*3 = 1`; irrational results fall back to `f64` or fail  
+//! - **Decimal arithmetic:** [`eval_decimal`] computes in base 10 at a fixed number of decimal
+//!   places, rounding every result half-even, half-up or down  
+//! - **Big integers:** [`eval_integer`] keeps integer-only expressions exact at any size, so
+//!   `2^200` and `30!` have every digit; division or fractions fall back to `f64`  
+//! - **Complex numbers:** [`eval_complex`] with the imaginary unit `i`, so `sqrt(-1) = i` and
+//!   `log(-1) = iπ`; [`ParseOptions::complex`] also reads `3 + 4i`  
+//! - **Other number types:** [`eval_as`] evaluates in `f32`, `f64` or any type implementing
+//!   [`Numeric`]  
 //! - **Arithmetic inside special functions:** `log(10 + 5)`, `sqrt(2 + 7)`  
+//! - **Special functions inside expressions:** `2 + sqrt(4)`, `log(3) * log(5)`, `sqrt(sqrt(16))`  
 //! - **Sequential expressions:** `((2 + 3) * 4)`  
 //!
 //! ## Grammar Overview
 //!
 //! ```text
 //! Expression ::= Term { ("+" | "-") Term }
-//! Term       ::= Factor { ("*" | "/") Factor }
-//! Factor     ::= Number | "(" Expression ")" | Function | "-" Factor
-//! Function   ::= "log" "(" Expression ")" | "sqrt" "(" Expression ")"
+//! Term       ::= Factor { ("*" | "/" | "%" | "//") Factor }
+//! Factor     ::= "-" Factor | Power
+//! Power      ::= Postfix [ ("^" | "**") Factor ]
+//! Postfix    ::= Primary { "!" | "%" }
+//! Primary    ::= Imaginary | Number | "(" Expression ")" | Function | Name
+//! Imaginary  ::= Number "i"     (only with ParseOptions::complex)
+//! Number     ::= digits and "." [ ("e" | "E") [ "+" | "-" ] digits ]
+//! Function   ::= Name "(" [ Expression { "," Expression } ] ")"
+//! Statement  ::= Name "=" Expression | Expression "==" Expression | Expression
 //! ```
 //!
 //! ## Example Usage
@@ -31,41 +69,285 @@
 //! ```
 //!
 //! ```text
-//! Enter an arithmetic expression:
+//! Enter an arithmetic expression (:help for commands, :quit to leave):
 //! > sqrt(9) + log(10)
 //! Result: 5.302585092994046
+//! > rate = (ans -
+//! > 5)
+//! rate = 0.30258509299404634
+//! > :vars
+//! _ = 0.30258509299404634
+//! ans = 0.30258509299404634
+//! rate = 0.30258509299404634
+//! ```
+//!
+//! The prompt supports line editing and keeps its history in `~/.arythemetic_history`.
+//! Input with an unclosed `(` continues on the next line, `ans` is the previous result,
+//! and `:vars`, `:funcs`, `:help` and `:quit` are commands rather than expressions.
+//!
+//! Variables come from an [`Environment`] passed to [`eval_with`] or [`Context::eval_with`], so a
+//! formula can be parsed once and evaluated against different inputs:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{Environment, eval_with, parse};
+//!
+//! let formula = parse("price * (1 + rate)").unwrap();
+//! let mut env = Environment::new();
+//! env.set("rate", 0.2);
+//! env.set("price", 100.0);
+//! assert_eq!(eval_with(&formula, &env).unwrap(), 120.0);
+//! ```
+//!
+//! A [`Session`] keeps an environment across lines, the way `parse <filename>` reads a file:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::Session;
+//!
+//! let mut session = Session::new();
+//! session.run("rate = 0.2").unwrap();
+//! assert_eq!(session.run("100 * (1 + rate)").unwrap(), 120.0);
+//! assert_eq!(session.run("_ - 100").unwrap(), 20.0);
+//! ```
+//!
+//! [`eval_exact`] evaluates the same tree with exact rationals instead of `f64`. [`Inexact`]
+//! chooses what happens when a result is irrational, e.g. `sqrt(2)` or `pi`:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{Inexact, eval_exact, parse};
+//!
+//! let expr = parse("1/3 * 3 + 0.1 + 0.2").unwrap();
+//! assert_eq!(eval_exact(&expr, Inexact::Error).unwrap().to_string(), "13/10");
+//!
+//! let expr = parse("sqrt(2)").unwrap();
+//! assert!(eval_exact(&expr, Inexact::Error).is_err());
+//! assert!(!eval_exact(&expr, Inexact::Float).unwrap().is_exact());
+//! ```
+//!
+//! For money, [`eval_decimal`] keeps a fixed number of decimal places. Each
+//! operation, division included, is rounded to that scale with the chosen [`Rounding`]:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{
+//!     DecimalOptions, Rounding, eval_decimal, parse,
+//! };
+//!
+//! let expr = parse("10 / 3 * 3").unwrap();
+//! let cents = DecimalOptions::new(2, Rounding::HalfEven);
+//! assert_eq!(eval_decimal(&expr, &cents).unwrap().to_string(), "9.99");
+//!
+//! let expr = parse("2.675 * 1").unwrap();
+//! assert_eq!(eval_decimal(&expr, &cents).unwrap().to_string(), "2.68");
+//! let down = DecimalOptions::new(2, Rounding::Down);
+//! assert_eq!(eval_decimal(&expr, &down).unwrap().to_string(), "2.67");
+//! ```
+//!
+//! [`eval_integer`] never overflows while an expression only involves integers, and
+//! reports whether the result is such an exact [`Integer`]:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{eval_integer, parse};
+//!
+//! let value = eval_integer(&parse("30!").unwrap()).unwrap();
+//! assert_eq!(value.to_string(), "265252859812191058636308480000000");
+//! assert!(value.is_exact());
+//! assert!(!eval_integer(&parse("30! / 7").unwrap()).unwrap().is_exact());
+//! ```
+//!
+//! [`eval_complex`] is opt-in: real results stay as in [`eval`], and values that have no real
+//! result become [`Complex`] numbers, printed as `a + bi`:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{
+//!     ParseOptions, eval_complex, parse, parse_with,
+//! };
+//!
+//! let root = eval_complex(&parse("sqrt(-4)").unwrap()).unwrap();
+//! assert_eq!(root.to_string(), "0 + 2i");
+//!
+//! let options = ParseOptions {
+//!     complex: true,
+//!     ..ParseOptions::default()
+//! };
+//! let z = eval_complex(&parse_with("(3 + 4i) / (1 - 2i)", &options).unwrap()).unwrap();
+//! assert_eq!(z.to_string(), "-1 + 2i");
+//! ```
+//!
+//! The evaluator is generic over the [`Numeric`] trait: [`eval`] is `eval_as::<f64>`,
+//! [`eval_as`] with `f32` computes in single precision, and a host application can plug in
+//! its own number type by implementing [`Numeric`] for it:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{eval_as, parse};
+//!
+//! let expr = parse("1 / 3").unwrap();
+//! assert_eq!(eval_as::<f32>(&expr).unwrap(), 1.0f32 / 3.0);
+//! ```
+//!
+//! Exact, decimal, integer and complex evaluation go through the same evaluator with an
+//! arithmetic of their own in place of a [`Numeric`] type, since they depend on a setting
+//! such as the number of decimal places and may move to `f64` part way through an
+//! expression. Their operations fail with [`EvalError::NotExact`] and [`EvalError::NotReal`],
+//! reported as [`ParseError::NotExact`] and [`ParseError::NotReal`] at the operation.
+//!
+//! Functions live in the context's [`FunctionRegistry`]. Host applications can register their
+//! own as closures with a declared [`Arity`]; a closure reports failures with [`EvalError`]:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{Arity, Context, parse};
+//!
+//! let mut ctx = Context::default();
+//! ctx.functions_mut()
+//!     .register("tax", Arity::Exact(1), |args| Ok(args[0] * 0.2));
+//! assert_eq!(ctx.eval(&parse("100 + tax(100)").unwrap()).unwrap(), 120.0);
 //! ```
 //!
 //! ## Command-Line Interface
 //!
 //! The CLI provides several commands:
 //!
+//! - no arguments — start the interactive prompt described above  
 //! - `help` — display usage information  
 //! - `credits` — show author and project details  
-//! - `parse <filename>` — parse and evaluate expressions from a file  
+//! - `eval "<expr>"` — evaluate one expression and print only its value  
+//! - `parse <filename>` — parse and evaluate expressions from a file, one per line; variables
+//!   assigned on one line are visible on the following ones; `parse -` reads standard input  
 //!
-//! ## Grammar Rules Implemented
+//! `eval` and `parse` print errors to standard error and exit with a nonzero code when any
+//! line fails, so they can be used in shell pipelines. The exit codes are:
 //!
-//! 1. **Handle nested brackets:** `((x))`
-//! 2. **Handle expressions within brackets:** `(x)`
-//! 3. **Handle long expressions with multiple operators:** `x + y - z * w / v`
-//! 4. **Handle special function with inside expressions:** `log(x)`
-//! 5. **Handle special function with inside expressions:** `sqrt(x)`
+//! - `0` — every line was evaluated  
+//! - `1` — at least one expression failed to parse or evaluate  
+//! - `2` — the input could not be read, or the command line was wrong  
+//!
+//! `check <filename>` turns an expression file into a regression suite. A line may state its
+//! expected value as `sqrt(16) == 4` or with a trailing `2 + 3 * 4 # => 14` comment; `check`
+//! prints `PASS` or `FAIL` for each of them and a summary, and exits with `1` if any failed.
+//! Values match when they differ by at most `--tolerance` (default `1e-9`), relative to the
+//! expected value when it is larger than 1. Lines without an expectation are still evaluated,
+//! so assignments keep working. `parse`, `eval` and the prompt fail a `==` line whose sides
+//! differ by more than the default tolerance, [`DEFAULT_TOLERANCE`].
+//!
+//! `--strict` makes `parse` and `check` stop at the first failing line. Bytes that are not valid UTF-8 do not
+//! abort the run: they are read as `U+FFFD` and reported as an unexpected token on their line.
+//!
+//!
+//! ```bash
+//! echo "sqrt(2) * 2" | cargo run -q -- parse -
+//! cargo run -q -- eval "2 ^ 10"
+//! ```
+//!
+//! `--format json|ndjson|csv|text` turns the output of `eval` and `parse` into one record per
+//! line with the line number, the source, the assigned variable, and either the value or the
+//! error (code, kind, message and span). Errors are then part of the output rather than stderr:
+//!
+//! ```text
+//! $ cargo run -q -- parse --format ndjson expressions.txt
+//! {"line":1,"source":"1 + 2","variable":null,"value":3,"error":null}
+//! {"line":2,"source":"1 / 0","variable":null,"value":null,"error":{"code":"E003","kind":"DivisionByZero","message":"division by zero at 4..5","span":{"start":4,"end":5}}}
+//! ```
+//!
+//! JSON has no infinity or NaN, so those values are written as the strings `"inf"`, `"-inf"`
+//! and `"NaN"`.
+//!
+//! ## Library Usage
+//!
+//! Parsing and evaluation are separate steps. [`parse`] turns the source into an
+//! [`Expr`] tree following the grammar above, and [`eval`] computes its value:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{Expr, eval, parse};
+//!
+//! let expr = parse("sqrt(2 + 7) * 2").unwrap();
+//! assert!(matches!(expr, Expr::Binary { .. }));
+//! assert_eq!(eval(&expr).unwrap(), 6.0);
+//! ```
+//!
+//! [`parse_expression`] does both at once.
+//!
+//! Names such as `pi` are resolved at evaluation time from a [`Context`]. [`eval`] uses
+//! [`Context::default`]; pass your own to add constants or to reject `inf` and `nan`:
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{Context, parse};
+//!
+//! let mut ctx = Context::strict();
+//! ctx.set_constant("g", 9.81);
+//! assert_eq!(ctx.eval(&parse("2 * g").unwrap()).unwrap(), 19.62);
+//! assert!(ctx.eval(&parse("inf").unwrap()).is_err());
+//! ```
 //!
 //! PS : all rules handle whitespaces appropriately and mind the domain of functions.
 //!
 //! ## Error Handling
 //!
-//! - Invalid or undefined expressions (e.g. `log(0)` or `sqrt(-1)`) trigger an `InvalidExpression` error.  
-//! - Errors are managed using [`thiserror`](https://docs.rs/thiserror/latest/thiserror/) in the library and [`anyhow`](https://docs.rs/anyhow/latest/anyhow/) in tests.
+//! Every [`ParseError`] variant carries a [`Span`] (byte offsets into the source) and its
+//! `Display` says what went wrong and where:
+//!
+//! - `UnexpectedToken` — a character the grammar does not allow, e.g. `1 ++ 2`  
+//! - `UnbalancedParen` — a `(` without a `)` or the other way round  
+//! - `DivisionByZero` — the right operand of `/`, `%` or `//` is zero  
+//! - `DomainError` — a function argument outside its domain, e.g. `log(0)` or `sqrt(-1)`  
+//! - `UnknownFunction` — a call to a function that does not exist  
+//! - `EmptyInput` — nothing to parse  
+//! - `InvalidNumber` — a malformed literal such as `1.2.3`  
+//! - `UnknownVariable` — a name that is neither a variable nor a constant  
+//! - `ArityMismatch` — a function called with the wrong number of arguments, e.g. `atan2(1)`  
+//! - `FunctionFailed` — a registered function returned [`EvalError::Custom`]  
+//! - `NotExact` — an irrational result in [`eval_exact`] with [`Inexact::Error`]  
+//! - `NotReal` — a complex value given to an operation that needs a real one in
+//!   [`eval_complex`], e.g. `floor(i)`  
+//! - `ExpectationFailed` — the two sides of `value == expected` differ by more than the
+//!   [`Session`]'s tolerance  
+//! - `TooDeep` — more than [`MAX_NESTING`] nested brackets, calls, signs or powers, or a
+//!   chain of operators longer than [`MAX_DEPTH`]  
+//!
+//! ```
+//! use arythemetic_expressions_parser_kharchenko_kma::{ParseError, Span, parse_expression};
+//!
+//! let err = parse_expression("1 + log(0)").unwrap_err();
+//! assert!(matches!(err, ParseError::DomainError { .. }));
+//! assert_eq!(err.span(), Span::new(4, 10));
+//! assert_eq!(err.to_string(), "log is undefined for argument 0 at 4..10");
+//! ```
+//!
+//! [`render_diagnostic`] turns an error into a report with an error code, the offending line
+//! with the failing span underlined, and a hint. The CLI prints these for failing lines:
+//!
+//! ```text
+//! error[E005]: unknown function 'sqr' at 4..10
+//!  --> expressions.txt:3:5
+//!   |
+//! 3 | 2 + sqr(4)
+//!   |     ^^^^^^ not a known function
+//!   = hint: did you mean 'sqrt'?
+//! ```
+//!
+//! Errors are managed using [`thiserror`](https://docs.rs/thiserror/latest/thiserror/) in the library and [`anyhow`](https://docs.rs/anyhow/latest/anyhow/) in tests.
 //!
 //! ## Example Input File (`expressions.txt`)
 //!
 //! ```text
-//! 2 + 3 * 4
+//! # reference values for the README
+//! 2 + 3 * 4           # => 14
 //! log(10)
-//! sqrt(16)
+//! sqrt(16) == 4
 //! (2 + 3) * (4 - 1)
+//! rate = 0.2          # VAT
+//! 100 * (1 + rate)    # => 120
+//! ans / 2             # => 60
+//! ```
+//!
+//! `#` starts a comment anywhere on a line. `//` also starts a comment, but only at the
+//! beginning of a line, since elsewhere it is floor division. See [`strip_comment`].
+//!
+//! ## Cargo Features
+//!
+//! - `serde` — `Serialize` and `Deserialize` for [`Expr`], [`Statement`], [`ParseResult`],
+//!   [`ParseError`], [`EvalError`] and the types they contain, so a parsed formula can be
+//!   stored as JSON and evaluated later without parsing it again:
+//!
+//! ```toml
+//! arythemetic_expressions_parser_kharchenko_kma = { version = "0.1", features = ["serde"] }
 //! ```
 //!
 //! ## Makefile
@@ -84,387 +366,57 @@
 //!
 //! ## Summary
 //!
-//! The parser reads arithmetic expressions, constructs a syntax tree according to grammar rules,
-//! and computes the resulting numeric value while handling errors gracefully.
-
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-pub enum ParseError {
-    #[error("Invalid expression")]
-    InvalidExpression,
-}
+//! The parser reads arithmetic expressions, constructs a syntax tree according to grammar rules
+//! (using [`peg`](https://docs.rs/peg/latest/peg/)), and computes the resulting numeric value
+//! while handling errors gracefully.
+
+mod ast;
+mod complex;
+mod context;
+mod decimal;
+mod diagnostic;
+mod environment;
+mod error;
+mod eval;
+mod exact;
+mod functions;
+mod integer;
+mod numeric;
+mod parser;
+mod session;
+
+pub use ast::{BinaryOp, Expr, Statement, UnaryOp};
+pub use complex::{Complex, eval_complex};
+pub use context::Context;
+pub use decimal::{Decimal, DecimalOptions, Rounding, eval_decimal};
+pub use diagnostic::{Diagnostic, render_diagnostic};
+pub use environment::Environment;
+pub use error::{EvalError, ParseError, Span};
+pub use eval::{eval, eval_as, eval_with};
+pub use exact::{Exact, Inexact, eval_exact};
+pub use functions::{Arity, FunctionRegistry, RegisteredFunction};
+pub use integer::{Integer, eval_integer};
+pub use numeric::Numeric;
+pub use parser::{
+    MAX_DEPTH, MAX_NESTING, ParseOptions, parse, parse_statement, parse_statement_with, parse_with,
+    strip_comment,
+};
+pub use session::{DEFAULT_TOLERANCE, Session};
 
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ParseResult {
     pub result: f64,
     pub operands: Vec<f64>,
-    pub operators: Vec<char>,
-}
-
-fn check_balanced_brackets(s: &str) -> bool {
-    let mut balance = 0;
-    for c in s.chars() {
-        if c == '(' {
-            balance += 1;
-        } else if c == ')' {
-            balance -= 1;
-            if balance < 0 {
-                return false;
-            }
-        }
-    }
-    balance == 0
-}
-
-//rule 0: handle ((x))
-fn remove_outer_brackets(s: &str) -> Option<&str> {
-    if s.starts_with('(') && s.ends_with(')') {
-        let mut balance = 0;
-        // let mut i = 0;
-        let chars = s.chars();
-
-        // while let Some(c) = chars.next() {
-        for (i, c) in chars.enumerate() {
-            if c == '(' {
-                balance += 1;
-            } else if c == ')' {
-                balance -= 1;
-            }
-
-            if balance == 0 && i < s.len() - 1 {
-                return None;
-            }
-            // i += 1;
-        }
-
-        if balance == 0 {
-            return Some(&s[1..s.len() - 1]);
-        }
-    }
-    None
-}
-
-// fn has_minus(s: &str) -> bool {
-//     return (s.contains("+(-") || s.contains("-(-") || s.contains("*(-") || s.contains("/(-"))
-//         && s.ends_with(')');
-// }
-
-// fn simplify_minus(s: &str) -> String {
-//     let mut new_s = s.replace("+(-", "-");
-//     new_s = new_s.replace("-(-", "+");
-//     new_s = new_s.replace("*(-", "*-");
-//     new_s = new_s.replace("/(-", "/-");
-
-//     if new_s.ends_with(')') {
-//         new_s.pop();
-//     }
-
-//     return new_s;
-// }
-
-// fn has_minus_at_start(s: &str) -> bool {
-//     return s.starts_with("-(") && s.ends_with(')');
-// }
-
-// fn handle_minus_at_start(s: &str) -> Result<ParseResult, ParseError> {
-//     let inner = &s[2..s.len() - 1];
-//     let inner_result = parse_expression(inner)?;
-
-//     return Ok(ParseResult {
-//         result: -inner_result.result,
-//         operands: inner_result.operands,
-//         operators: inner_result.operators,
-//     });
-// }
-
-// fn pasre_simple_expression(s: &str) -> Result<ParseResult, ParseError> {
-//     if s.contains('+') {
-//         let parts: Vec<&str> = s.split('+').collect();
-//         if parts.len() != 2 {
-//             return Err(ParseError::InvalidExpression);
-//         }
-
-//         let left: f64 = parts[0]
-//             .trim()
-//             .parse()
-//             .map_err(|_| ParseError::InvalidExpression)?;
-//         let right: f64 = parts[1]
-//             .trim()
-//             .parse()
-//             .map_err(|_| ParseError::InvalidExpression)?;
-
-//         Ok(ParseResult {
-//             result: left + right,
-//             operands: vec![left, right],
-//             operators: vec!['+'],
-//         })
-//     } else if s.contains('*') {
-//         let parts: Vec<&str> = s.split('*').collect();
-//         if parts.len() != 2 {
-//             return Err(ParseError::InvalidExpression);
-//         }
-
-//         let left: f64 = parts[0]
-//             .trim()
-//             .parse()
-//             .map_err(|_| ParseError::InvalidExpression)?;
-//         let right: f64 = parts[1]
-//             .trim()
-//             .parse()
-//             .map_err(|_| ParseError::InvalidExpression)?;
-
-//         Ok(ParseResult {
-//             result: left * right,
-//             operands: vec![left, right],
-//             operators: vec!['*'],
-//         })
-//     } else if s.contains('/') {
-//         let parts: Vec<&str> = s.split('/').collect();
-//         if parts.len() != 2 {
-//             return Err(ParseError::InvalidExpression);
-//         }
-
-//         let left: f64 = parts[0]
-//             .trim()
-//             .parse()
-//             .map_err(|_| ParseError::InvalidExpression)?;
-//         let right: f64 = parts[1]
-//             .trim()
-//             .parse()
-//             .map_err(|_| ParseError::InvalidExpression)?;
-
-//         if right == 0.0 {
-//             return Err(ParseError::InvalidExpression);
-//         }
-
-//         Ok(ParseResult {
-//             result: left / right,
-//             operands: vec![left, right],
-//             operators: vec!['/'],
-//         })
-//     } else if s.contains('-') {
-//         let parts: Vec<&str> = s.split('-').collect();
-//         if parts.len() != 2 {
-//             return Err(ParseError::InvalidExpression);
-//         }
-
-//         let left: f64 = parts[0]
-//             .trim()
-//             .parse()
-//             .map_err(|_| ParseError::InvalidExpression)?;
-//         let right: f64 = parts[1]
-//             .trim()
-//             .parse()
-//             .map_err(|_| ParseError::InvalidExpression)?;
-
-//         Ok(ParseResult {
-//             result: left - right,
-//             operands: vec![left, right],
-//             operators: vec!['-'],
-//         })
-//     } else {
-//         Err(ParseError::InvalidExpression)
-//     }
-// }
-
-fn handle_long_expression(s: &str) -> Result<ParseResult, ParseError> {
-    let mut nums = Vec::new();
-    let mut ops = Vec::new();
-    let mut current_num = String::new();
-
-    let mut k = 0;
-    let chars: Vec<char> = s.chars().collect();
-
-    while k < chars.len() {
-        let i = chars[k];
-
-        if i.is_whitespace() {
-            k += 1;
-            continue;
-        }
-
-        //rule 1: handle (x)
-        if i == '(' {
-            let mut d = 1;
-            let mut start = k + 1;
-            while start < chars.len() && d > 0 {
-                if chars[start] == '(' {
-                    d += 1;
-                } else if chars[start] == ')' {
-                    d -= 1;
-                }
-                start += 1;
-            }
-
-            if d != 0 {
-                return Err(ParseError::InvalidExpression);
-            }
-
-            let inside = &s[k + 1..start - 1];
-            let inside_res = handle_long_expression(inside)?;
-            // nums.push(inside_res.result);
-            // current_num.clear();
-            current_num.push_str(&inside_res.result.to_string());
-            k = start;
-            continue;
-        }
-
-        // rule 2: handle operators
-        if i == '+' || i == '-' || i == '*' || i == '/' {
-            if current_num.trim().is_empty() {
-                if i == '-' {
-                    current_num.push(i);
-                    k += 1;
-                    continue;
-                } else {
-                    return Err(ParseError::InvalidExpression);
-                }
-            }
-
-            let num: f64 = current_num
-                .parse()
-                .map_err(|_| ParseError::InvalidExpression)?;
-            nums.push(num);
-            ops.push(i);
-            current_num.clear();
-        } else {
-            current_num.push(i);
-        }
-
-        k += 1;
-    }
-
-    if current_num.trim().is_empty() {
-        return Err(ParseError::InvalidExpression);
-    }
-
-    nums.push(
-        current_num
-            .trim()
-            .parse()
-            .map_err(|_| ParseError::InvalidExpression)?,
-    );
-
-    //rule 3: evaluate expression
-    // handle * and / first
-    let mut j = 0;
-    while j < ops.len() {
-        if ops[j] == '*' || ops[j] == '/' {
-            let left = nums[j];
-            let right = nums[j + 1];
-            let res: f64 = if ops[j] == '*' {
-                left * right
-            } else {
-                if right == 0.0 {
-                    return Err(ParseError::InvalidExpression);
-                }
-                left / right
-            };
-            nums[j] = res;
-            nums.remove(j + 1);
-            ops.remove(j);
-        } else {
-            j += 1;
-        }
-    }
-
-    // then handle + and -
-    let mut result = nums[0];
-    for k in 0..ops.len() {
-        let right = nums[k + 1];
-        if ops[k] == '+' {
-            result += right;
-        } else if ops[k] == '-' {
-            result -= right;
-        } else {
-            return Err(ParseError::InvalidExpression);
-        }
-    }
-
-    Ok(ParseResult {
-        result,
-        operands: nums,
-        operators: ops,
-    })
-}
-
-//rule 4: handle log(x)
-fn handle_log(s: &str) -> Result<ParseResult, ParseError> {
-    let s = s.trim();
-    if let Some(inner) = s.strip_prefix("log(").and_then(|s| s.strip_suffix(")")) {
-        let inner_res = parse_expression(inner)?;
-        if inner_res.result <= 0.0 {
-            return Err(ParseError::InvalidExpression);
-        }
-        Ok(ParseResult {
-            result: inner_res.result.ln(),
-            operands: inner_res.operands,
-            operators: inner_res.operators,
-        })
-    } else {
-        Err(ParseError::InvalidExpression)
-    }
-}
-
-//rule 5: handle sqrt(x)
-fn handle_sqrt(s: &str) -> Result<ParseResult, ParseError> {
-    let s = s.trim();
-    if let Some(inner) = s.strip_prefix("sqrt(").and_then(|s| s.strip_suffix(")")) {
-        let inner_res = parse_expression(inner)?;
-        if inner_res.result < 0.0 {
-            return Err(ParseError::InvalidExpression);
-        }
-        Ok(ParseResult {
-            result: inner_res.result.sqrt(),
-            operands: inner_res.operands,
-            operators: inner_res.operators,
-        })
-    } else {
-        Err(ParseError::InvalidExpression)
-    }
+    pub operators: Vec<BinaryOp>,
 }
 
 // the function to parse expression
 pub fn parse_expression(s: &str) -> Result<ParseResult, ParseError> {
-    let s = s.trim();
-
-    if let Some(inner) = remove_outer_brackets(s) {
-        return parse_expression(inner);
-    }
-
-    // if has_minus(s) {
-    //     let simplified = simplify_minus(s);
-    //     return parse_expression(&simplified);
-    // }
-
-    // if has_minus_at_start(s) {
-    //     return handle_minus_at_start(s);
-    // }
-
-    if s.trim().is_empty() || !check_balanced_brackets(s) {
-        return Err(ParseError::InvalidExpression);
-    } else {
-        if let Ok(num) = s.parse::<f64>() {
-            return Ok(ParseResult {
-                result: num,
-                operands: vec![num],
-                operators: vec![],
-            });
-        }
-
-        if s.starts_with("log(") && s.ends_with(")") {
-            return handle_log(s);
-        }
-
-        if s.starts_with("sqrt(") && s.ends_with(")") {
-            return handle_sqrt(s);
-        }
-
-        if s.contains('+') || s.contains('-') || s.contains('*') || s.contains('/') {
-            return handle_long_expression(s);
-        }
-    }
-
-    Err(ParseError::InvalidExpression)
+    let expr = parse(s)?;
+    Ok(ParseResult {
+        result: eval(&expr)?,
+        operands: expr.operands(),
+        operators: expr.operators(),
+    })
 }
@@ -1,37 +1,104 @@
+mod check;
+mod output;
+mod repl;
+
 use arythemetic_expressions_parser_kharchenko_kma::*;
+use output::{Format, Output, Record};
 use std::fs::File;
 use std::io::{self, BufRead};
+use std::process;
+
+/// Some expressions failed to parse or evaluate.
+const EXIT_FAILED: i32 = 1;
+/// The input could not be read, or the command line was wrong.
+const EXIT_USAGE: i32 = 2;
 
 fn main() {
-    println!("Welcome to Arythmetic Expression Parser CLI!");
     let args: Vec<String> = std::env::args().collect();
 
     if args.len() < 2 {
-        print_help();
+        println!("Welcome to Arythmetic Expression Parser CLI!");
+        if let Err(e) = repl::run() {
+            eprintln!("prompt failed: {}", e);
+            process::exit(EXIT_USAGE);
+        }
         return;
     }
 
+    // `eval` and `parse` only print results so they can be used in pipelines
     if args[1] == "help" {
         print_help();
     } else if args[1] == "credits" {
         print_credits();
-    } else if args[1] == "parse" {
-        if args.len() < 3 {
-            println!("provide a name of the file to parse");
-            return;
+    } else if args[1] == "eval" || args[1] == "parse" || args[1] == "check" {
+        let (options, operands) = match split_options(&args[2..]) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(EXIT_USAGE);
+            }
+        };
+        let ok = if args[1] == "eval" {
+            if operands.is_empty() {
+                eprintln!("provide an expression to evaluate, e.g. eval \"2 + 3\"");
+                process::exit(EXIT_USAGE);
+            }
+            eval_expression(&operands.join(" "), options.format)
+        } else {
+            if operands.len() != 1 {
+                eprintln!(
+                    "provide a name of the file to {}, or - to read from stdin",
+                    args[1]
+                );
+                process::exit(EXIT_USAGE);
+            }
+            let result = if args[1] == "check" {
+                open(&operands[0]).and_then(|reader| {
+                    check::run(
+                        reader,
+                        origin(&operands[0]),
+                        options.tolerance,
+                        options.strict,
+                    )
+                })
+            } else {
+                parse_file(&operands[0], &options)
+            };
+            match result {
+                Ok(ok) => ok,
+                Err(e) => {
+                    eprintln!("Could not read {}: {}", operands[0], e);
+                    process::exit(EXIT_USAGE);
+                }
+            }
+        };
+        if !ok {
+            process::exit(EXIT_FAILED);
         }
-        parse_file(&args[2]);
     } else {
-        println!("unknown enter: {}", args[1]);
+        eprintln!("unknown enter: {}", args[1]);
         print_help();
+        process::exit(EXIT_USAGE);
     }
 }
 
 fn print_help() {
+    println!("Welcome to Arythmetic Expression Parser CLI!");
     println!("info:");
-    println!("parse    - parse expressions from a file");
+    println!("(none)   - start an interactive prompt");
+    println!("eval     - evaluate one expression: eval \"2 + 3\"");
+    println!("parse    - parse expressions from a file, or from stdin with parse -");
+    println!(
+        "check    - compare lines such as `sqrt(16) == 4` or `2+3*4 # => 14` with their results"
+    );
     println!("help     - show this help message");
     println!("credits  - show credits");
+    println!("--format - output of eval and parse: text (default), json, ndjson or csv");
+    println!("--strict - stop parse or check at the first line that fails");
+    println!("--tolerance <x> - allowed difference in check, relative above 1 (default 1e-9)");
+    println!(
+        "exit code: 0 - all lines ok, 1 - some lines failed, 2 - unreadable input or wrong usage"
+    );
 }
 
 fn print_credits() {
@@ -39,22 +106,144 @@ fn print_credits() {
     println!("CLI version");
 }
 
-fn parse_file(filename: &str) {
-    let file = File::open(filename);
-    if file.is_err() {
-        println!("Could not open file: {}", filename);
-        return;
+struct Options {
+    format: Format,
+    strict: bool,
+    tolerance: f64,
+}
+
+// takes `--format`, `--strict` and `--tolerance` out of the arguments
+fn split_options(args: &[String]) -> Result<(Options, Vec<String>), String> {
+    let mut options = Options {
+        format: Format::Text,
+        strict: false,
+        tolerance: DEFAULT_TOLERANCE,
+    };
+    let mut operands = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--format" {
+            let name = args.next().ok_or("--format needs a value")?;
+            options.format = name.parse()?;
+        } else if let Some(name) = arg.strip_prefix("--format=") {
+            options.format = name.parse()?;
+        } else if arg == "--strict" {
+            options.strict = true;
+        } else if arg == "--tolerance" {
+            let value = args.next().ok_or("--tolerance needs a value")?;
+            options.tolerance = parse_tolerance(value)?;
+        } else if let Some(value) = arg.strip_prefix("--tolerance=") {
+            options.tolerance = parse_tolerance(value)?;
+        } else {
+            operands.push(arg.clone());
+        }
+    }
+    Ok((options, operands))
+}
+
+fn parse_tolerance(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(tolerance) if tolerance >= 0.0 && tolerance.is_finite() => Ok(tolerance),
+        _ => Err(format!(
+            "invalid tolerance '{}', expected a number >= 0",
+            value
+        )),
+    }
+}
+
+// in text format prints the value alone, so the output can be used by another command
+fn eval_expression(source: &str, format: Format) -> bool {
+    let result = parse_statement(source).and_then(|statement| {
+        let value = Session::new().execute(&statement)?;
+        Ok((statement, value))
+    });
+    if format == Format::Text {
+        match &result {
+            Ok((_, value)) => println!("{}", value),
+            Err(e) => eprintln!("{}", Diagnostic::new(source, e)),
+        }
+        return result.is_ok();
     }
 
-    let reader = io::BufReader::new(file.unwrap());
-    for (i, line) in reader.lines().enumerate() {
-        let line = line.unwrap();
+    let mut output = Output::new(format, "<eval>");
+    output.record(&record(1, source, &result));
+    output.finish();
+    result.is_ok()
+}
+
+// returns whether every line was evaluated successfully
+fn parse_file(filename: &str, options: &Options) -> io::Result<bool> {
+    let reader = open(filename)?;
+    let mut session = Session::new();
+    let mut output = Output::new(options.format, origin(filename));
+    let mut ok = true;
+    read_lines(reader, |number, line| {
+        let line = strip_comment(line).trim_end();
         if line.trim().is_empty() {
-            continue;
+            return true;
         }
-        match parse_expression(line.trim()) {
-            Ok(res) => println!("Line {}: {} = {}", i + 1, line, res.result),
-            Err(e) => println!("Line {}: {} -> Error: {:?}", i + 1, line, e),
+        let result = parse_statement(line).and_then(|statement| {
+            let value = session.execute(&statement)?;
+            Ok((statement, value))
+        });
+        ok &= result.is_ok();
+        output.record(&record(number, line, &result));
+        ok || !options.strict
+    })?;
+    output.finish();
+    Ok(ok)
+}
+
+// `-` stands for stdin
+fn open(filename: &str) -> io::Result<Box<dyn BufRead>> {
+    if filename == "-" {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    Ok(Box::new(io::BufReader::new(File::open(filename)?)))
+}
+
+fn origin(filename: &str) -> &str {
+    if filename == "-" { "<stdin>" } else { filename }
+}
+
+// calls `f` with the number and text of every line until it returns false
+fn read_lines(mut reader: impl BufRead, mut f: impl FnMut(usize, &str) -> bool) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    let mut number = 0;
+    loop {
+        bytes.clear();
+        if reader.read_until(b'\n', &mut bytes)? == 0 {
+            return Ok(());
+        }
+        number += 1;
+        // bytes that are not UTF-8 become U+FFFD, which the parser then reports with its position
+        let line = String::from_utf8_lossy(&bytes);
+        if !f(number, line.trim_end_matches(['\n', '\r'])) {
+            return Ok(());
         }
     }
 }
+
+fn record<'a>(
+    line: usize,
+    source: &'a str,
+    result: &'a Result<(Statement, f64), ParseError>,
+) -> Record<'a> {
+    match result {
+        Ok((statement, value)) => Record {
+            line,
+            source,
+            variable: match statement {
+                Statement::Assign { name, .. } => Some(name),
+                Statement::Expect { .. } | Statement::Expr(_) => None,
+            },
+            outcome: Ok(*value),
+        },
+        Err(e) => Record {
+            line,
+            source,
+            variable: None,
+            outcome: Err(e),
+        },
+    }
+}
@@ -0,0 +1,243 @@
+//! Number types the evaluator can compute with.
+
+use crate::eval::factorial;
+use crate::functions::power;
+use crate::{EvalError, RegisteredFunction};
+
+/// A number type for [`eval_as`](crate::eval_as).
+///
+/// The evaluator only uses these operations, so any type implementing them can
+/// be plugged in. Operations that can fail check their domain and report
+/// [`EvalError::DivisionByZero`] or [`EvalError::Domain`]; the evaluator adds
+/// the name of the operation and its span.
+///
+/// Variables, constants and registered functions work on `f64`, so they reach
+/// the type through [`Numeric::from_f64`] and [`Numeric::to_f64`]; the built-in
+/// constants can have more digits through [`Numeric::constant`]. Methods with
+/// a default go through `f64` as well; override them when the type can do better.
+pub trait Numeric: Sized + Clone {
+    /// Reads the source text of a number literal, such as `12.5` or `.5`;
+    /// `None` when it is not a valid number.
+    fn from_literal(literal: &str) -> Option<Self>;
+
+    /// The nearest value to `x`.
+    fn from_f64(x: f64) -> Self;
+
+    /// The nearest `f64`.
+    fn to_f64(&self) -> f64;
+
+    fn add(&self, rhs: &Self) -> Self;
+
+    fn sub(&self, rhs: &Self) -> Self;
+
+    fn mul(&self, rhs: &Self) -> Self;
+
+    /// Fails with [`EvalError::DivisionByZero`] when `rhs` is zero.
+    fn div(&self, rhs: &Self) -> Result<Self, EvalError>;
+
+    /// `self` raised to `exp`. Zero to a negative power is
+    /// [`EvalError::DivisionByZero`]; a power without a value in this type,
+    /// such as a negative base with a fractional exponent, is [`EvalError::Domain`].
+    fn pow(&self, exp: &Self) -> Result<Self, EvalError>;
+
+    /// Fails with [`EvalError::Domain`] outside the domain, e.g. for `x < 0` in `f64`.
+    fn sqrt(&self) -> Result<Self, EvalError>;
+
+    /// The natural logarithm; fails with [`EvalError::Domain`] outside the
+    /// domain, e.g. for `x <= 0` in `f64`.
+    fn ln(&self) -> Result<Self, EvalError>;
+
+    /// The built-in constant `name` (`pi`, `e`, `tau`, `inf` or `nan`), or `None`
+    /// to use its `f64` value. It is not asked for when a variable or a constant
+    /// set on the [`Context`](crate::Context) hides the built-in one.
+    fn constant(name: &str) -> Option<Self> {
+        let _ = name;
+        None
+    }
+
+    fn neg(&self) -> Self {
+        Self::from_f64(0.0).sub(self)
+    }
+
+    /// The remainder of truncating division, `7 % 3`.
+    fn rem(&self, rhs: &Self) -> Result<Self, EvalError> {
+        let (l, r) = (self.to_f64(), rhs.to_f64());
+        if r == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok(Self::from_f64(l % r))
+    }
+
+    /// Division rounded down, `7 // 2`.
+    fn floor_div(&self, rhs: &Self) -> Result<Self, EvalError> {
+        let (l, r) = (self.to_f64(), rhs.to_f64());
+        if r == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok(Self::from_f64((l / r).floor()))
+    }
+
+    /// `n!`, extended to non-integers with the gamma function.
+    fn factorial(&self) -> Result<Self, EvalError> {
+        let x = self.to_f64();
+        factorial(x)
+            .map(Self::from_f64)
+            .ok_or(EvalError::Domain { arg: x })
+    }
+
+    /// Calls `function`, registered as `name`, after its arity was checked.
+    ///
+    /// The built-in `sqrt`, `ln`, `log(x)` and `pow` use the methods above;
+    /// every other function is computed in `f64`.
+    fn call(name: &str, function: &RegisteredFunction, args: &[Self]) -> Result<Self, EvalError> {
+        if function.is_builtin() {
+            match (name, args) {
+                ("sqrt", [x]) => return x.sqrt(),
+                ("ln" | "log", [x]) => return x.ln(),
+                ("pow", [x, y]) => return x.pow(y),
+                _ => {}
+            }
+        }
+        let floats: Vec<f64> = args.iter().map(Self::to_f64).collect();
+        function.call(&floats).map(Self::from_f64)
+    }
+}
+
+impl Numeric for f64 {
+    fn from_literal(literal: &str) -> Option<Self> {
+        literal.parse().ok()
+    }
+
+    fn from_f64(x: f64) -> Self {
+        x
+    }
+
+    fn to_f64(&self) -> f64 {
+        *self
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        self + rhs
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        self - rhs
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, EvalError> {
+        if *rhs == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok(self / rhs)
+    }
+
+    fn pow(&self, exp: &Self) -> Result<Self, EvalError> {
+        power(*self, *exp)
+    }
+
+    fn sqrt(&self) -> Result<Self, EvalError> {
+        if *self >= 0.0 {
+            Ok(f64::sqrt(*self))
+        } else {
+            Err(EvalError::Domain { arg: *self })
+        }
+    }
+
+    fn ln(&self) -> Result<Self, EvalError> {
+        if *self > 0.0 {
+            Ok(f64::ln(*self))
+        } else {
+            Err(EvalError::Domain { arg: *self })
+        }
+    }
+
+    fn neg(&self) -> Self {
+        -self
+    }
+
+    // every function works on f64, including replacements of the built-in ones
+    fn call(_: &str, function: &RegisteredFunction, args: &[Self]) -> Result<Self, EvalError> {
+        function.call(args)
+    }
+}
+
+impl Numeric for f32 {
+    fn from_literal(literal: &str) -> Option<Self> {
+        literal.parse().ok()
+    }
+
+    fn from_f64(x: f64) -> Self {
+        x as f32
+    }
+
+    fn to_f64(&self) -> f64 {
+        f64::from(*self)
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        self + rhs
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        self - rhs
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        self * rhs
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, EvalError> {
+        if *rhs == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok(self / rhs)
+    }
+
+    fn pow(&self, exp: &Self) -> Result<Self, EvalError> {
+        if *self == 0.0 && *exp < 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        if *self < 0.0 && exp.fract() != 0.0 {
+            return Err(EvalError::Domain { arg: self.to_f64() });
+        }
+        Ok(self.powf(*exp))
+    }
+
+    fn sqrt(&self) -> Result<Self, EvalError> {
+        if *self >= 0.0 {
+            Ok(f32::sqrt(*self))
+        } else {
+            Err(EvalError::Domain { arg: self.to_f64() })
+        }
+    }
+
+    fn ln(&self) -> Result<Self, EvalError> {
+        if *self > 0.0 {
+            Ok(f32::ln(*self))
+        } else {
+            Err(EvalError::Domain { arg: self.to_f64() })
+        }
+    }
+
+    fn neg(&self) -> Self {
+        -self
+    }
+
+    fn rem(&self, rhs: &Self) -> Result<Self, EvalError> {
+        if *rhs == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok(self % rhs)
+    }
+
+    fn floor_div(&self, rhs: &Self) -> Result<Self, EvalError> {
+        if *rhs == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok((self / rhs).floor())
+    }
+}
@@ -0,0 +1,198 @@
+//! Output formats of `eval` and `parse`.
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use arythemetic_expressions_parser_kharchenko_kma::{Diagnostic, ParseError};
+
+const CSV_HEADER: &str =
+    "line,source,variable,value,error_code,error_kind,error_message,span_start,span_end";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// `Line N: source = value` on stdout and diagnostics on stderr.
+    Text,
+    /// One JSON array holding every record.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+    /// A header row followed by one row per record.
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "ndjson" => Ok(Format::Ndjson),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!(
+                "unknown format '{}', expected json, ndjson, csv or text",
+                s
+            )),
+        }
+    }
+}
+
+/// The outcome of one evaluated line.
+pub struct Record<'a> {
+    /// 1-based line number in the input.
+    pub line: usize,
+    pub source: &'a str,
+    /// Name of the variable for an assignment such as `rate = 0.2`.
+    pub variable: Option<&'a str>,
+    pub outcome: Result<f64, &'a ParseError>,
+}
+
+/// Writes records to stdout (and diagnostics to stderr) in one [`Format`].
+pub struct Output<'a> {
+    format: Format,
+    origin: &'a str,
+    written: usize,
+}
+
+impl<'a> Output<'a> {
+    /// `origin` names the input in text diagnostics, e.g. the file name.
+    pub fn new(format: Format, origin: &'a str) -> Self {
+        match format {
+            Format::Json => print!("["),
+            Format::Csv => println!("{}", CSV_HEADER),
+            Format::Text | Format::Ndjson => {}
+        }
+        Output {
+            format,
+            origin,
+            written: 0,
+        }
+    }
+
+    pub fn record(&mut self, record: &Record) {
+        match self.format {
+            Format::Text => print_text(record, self.origin),
+            Format::Json => {
+                let separator = if self.written == 0 { "" } else { "," };
+                print!("{}\n  {}", separator, json_record(record));
+            }
+            Format::Ndjson => println!("{}", json_record(record)),
+            Format::Csv => println!("{}", csv_record(record)),
+        }
+        self.written += 1;
+    }
+
+    pub fn finish(self) {
+        if self.format == Format::Json {
+            let newline = if self.written == 0 { "" } else { "\n" };
+            println!("{}]", newline);
+        }
+    }
+}
+
+fn print_text(record: &Record, origin: &str) {
+    match (record.outcome, record.variable) {
+        (Ok(value), Some(name)) => println!("Line {}: {} = {}", record.line, name, value),
+        (Ok(value), None) => println!("Line {}: {} = {}", record.line, record.source, value),
+        (Err(e), _) => eprintln!(
+            "{}",
+            Diagnostic::new(record.source, e)
+                .origin(origin)
+                .first_line(record.line)
+        ),
+    }
+}
+
+fn json_record(record: &Record) -> String {
+    let mut out = format!(
+        "{{\"line\":{},\"source\":{},\"variable\":",
+        record.line,
+        json_string(record.source)
+    );
+    match record.variable {
+        Some(name) => out.push_str(&json_string(name)),
+        None => out.push_str("null"),
+    }
+    match record.outcome {
+        Ok(value) => {
+            let _ = write!(out, ",\"value\":{},\"error\":null}}", json_number(value));
+        }
+        Err(e) => {
+            let span = e.span();
+            let _ = write!(
+                out,
+                ",\"value\":null,\"error\":{{\"code\":\"{}\",\"kind\":\"{}\",\"message\":{},\"span\":{{\"start\":{},\"end\":{}}}}}}}",
+                e.code(),
+                e.kind(),
+                json_string(&e.to_string()),
+                span.start,
+                span.end
+            );
+        }
+    }
+    out
+}
+
+// JSON has no infinity or NaN, those are written as the strings "inf", "-inf" and "NaN"
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        value.to_string()
+    } else {
+        json_string(&value.to_string())
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn csv_record(record: &Record) -> String {
+    let variable = record.variable.unwrap_or_default();
+    match record.outcome {
+        Ok(value) => format!(
+            "{},{},{},{},,,,,",
+            record.line,
+            csv_field(record.source),
+            csv_field(variable),
+            value
+        ),
+        Err(e) => {
+            let span = e.span();
+            format!(
+                "{},{},{},,{},{},{},{},{}",
+                record.line,
+                csv_field(record.source),
+                csv_field(variable),
+                e.code(),
+                e.kind(),
+                csv_field(&e.to_string()),
+                span.start,
+                span.end
+            )
+        }
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
@@ -0,0 +1,415 @@
+//! Grammar-driven parser built with [`peg`].
+
+use std::cell::Cell;
+
+use crate::ast::{BinaryOp, Expr, Statement, UnaryOp};
+use crate::{ParseError, Span};
+
+/// Deepest nesting of brackets, calls, signs and powers; the parser recurses once for each.
+pub const MAX_NESTING: usize = 100;
+/// Deepest expression tree, reached by long chains such as `1 + 1 + … + 1`, which every
+/// evaluator walks recursively.
+pub const MAX_DEPTH: usize = 256;
+
+peg::parser! {
+    grammar expr_parser(state: &State) for str {
+        rule _ = quiet!{[' ' | '\t' | '\r' | '\n']*}
+
+        pub rule full() -> Expr
+            = _ e:expression() _ { e }
+
+        // Statement ::= Name "=" Expression | Expression "==" Expression | Expression
+        pub rule statement() -> Statement
+            = _ s:position!() name:identifier() _ "=" !"=" _ value:expression() _ {
+                let span = Span::new(s, value.span().end);
+                Statement::Assign { name: name.to_string(), value, span }
+            }
+            / _ value:expression() _ "==" _ expected:expression() _ {
+                let span = value.span().to(expected.span());
+                Statement::Expect { value, expected, span }
+            }
+            / e:full() { Statement::Expr(e) }
+
+        // Expression ::= Term { ("+" | "-") Term }
+        rule expression() -> Expr
+            = first:term() rest:(_ op:additive() _ t:term() { (op, t) })* {
+                fold_left(first, rest)
+            }
+
+        // Term ::= Factor { ("*" | "/" | "%" | "//") Factor }
+        rule term() -> Expr
+            = first:factor() rest:(_ op:multiplicative() _ f:factor() { (op, f) })* {
+                fold_left(first, rest)
+            }
+
+        // Factor ::= "-" Factor | Power
+        rule factor() -> Expr
+            = s:position!() "-" _ f:nested(s, <factor()>) {
+                let span = Span::new(s, f.span().end);
+                Expr::Unary { op: UnaryOp::Neg, operand: Box::new(f), span }
+            }
+            / power()
+
+        // Power ::= Postfix [ ("^" | "**") Factor ]
+        // the exponent is a Factor, which makes `^` right-associative
+        // and lets it take a negative exponent: `2^-1`
+        // the base is parsed once: trying `Postfix "^" Factor` before a plain `Postfix`
+        // would parse it twice at every bracket level, doubling the time per level
+        rule power() -> Expr
+            = base:postfix() exponent:(_ s:position!() ("^" / "**") _ e:nested(s, <factor()>) { e })? {
+                match exponent {
+                    Some(exponent) => binary(BinaryOp::Pow, base, exponent),
+                    None => base,
+                }
+            }
+
+        // Postfix ::= Primary { "!" | "%" }
+        rule postfix() -> Expr
+            = p:primary() ops:(_ op:postfix_operator() end:position!() { (op, end) })* {
+                ops.into_iter().fold(p, |operand, (op, end)| {
+                    let span = Span::new(operand.span().start, end);
+                    Expr::Unary { op, operand: Box::new(operand), span }
+                })
+            }
+
+        rule postfix_operator() -> UnaryOp
+            = "!" { UnaryOp::Factorial }
+            / percent_mode() "%" { UnaryOp::Percent }
+
+        // succeeds without consuming input when `%` means percent
+        rule percent_mode()
+            = quiet!{ {? if state.options.percent { Ok(()) } else { Err("percent mode") } } }
+
+        // Primary ::= Imaginary | Number | "(" Expression ")" | Function | Identifier
+        rule primary() -> Expr
+            = imaginary()
+            / number()
+            / s:position!() "(" _ e:nested(s, <expression()>) _ ")" end:position!() {
+                Expr::Group { inner: Box::new(e), span: Span::new(s, end) }
+            }
+            / function()
+            / s:position!() name:identifier() end:position!() {
+                Expr::Ident { name: name.to_string(), span: Span::new(s, end) }
+            }
+
+        // Function ::= Identifier "(" [ Expression { "," Expression } ] ")"
+        rule function() -> Expr
+            = s:position!() name:identifier() _ "(" _
+              args:nested(s, <expression() ** (_ "," _)>) _ ")" end:position!() {
+                Expr::Call { name: name.to_string(), args, span: Span::new(s, end) }
+            }
+
+        // Imaginary ::= Number "i", read as `Number * i`
+        rule imaginary() -> Expr
+            = complex_mode() n:number() s:position!() "i"
+              !['a'..='z' | 'A'..='Z' | '0'..='9' | '_'] end:position!() {
+                let unit = Expr::Ident { name: "i".to_string(), span: Span::new(s, end) };
+                binary(BinaryOp::Mul, n, unit)
+            }
+
+        // succeeds without consuming input when `4i` is an imaginary number
+        rule complex_mode()
+            = quiet!{ {? if state.options.complex { Ok(()) } else { Err("complex mode") } } }
+
+        // `r` one level deeper, in the construct starting at `s`;
+        // past MAX_NESTING levels the whole parse fails
+        rule nested<T>(s: usize, r: rule<T>) -> T
+            = enter(s) value:r()? leave() value:quiet!{ {? value.ok_or("") } } {
+                value
+            }
+
+        rule enter(s: usize)
+            = quiet!{ {? if state.enter(s) { Ok(()) } else { Err("") } } }
+
+        rule leave()
+            = { state.leave() }
+
+        rule identifier() -> &'input str
+            = quiet!{$(['a'..='z' | 'A'..='Z' | '_'] ['a'..='z' | 'A'..='Z' | '0'..='9' | '_']*)}
+            / expected!("identifier")
+
+        // the literal is taken greedily so that `1.2.3` is reported as one bad number;
+        // an exponent needs its digits, so `2e` stays a number followed by the name `e`
+        rule number() -> Expr
+            = s:position!() n:$(quiet!{['0'..='9' | '.']+ exponent()?} / expected!("number"))
+              end:position!() {?
+                n.parse()
+                    .map(|value| Expr::Number { value, literal: n.to_string(), span: Span::new(s, end) })
+                    .or(Err(INVALID_NUMBER))
+            }
+
+        rule exponent()
+            = ['e' | 'E'] ['+' | '-']? ['0'..='9']+
+
+        rule additive() -> BinaryOp
+            = "+" { BinaryOp::Add }
+            / "-" { BinaryOp::Sub }
+
+        rule multiplicative() -> BinaryOp
+            = "*" !"*" { BinaryOp::Mul }
+            / "//" { BinaryOp::FloorDiv }
+            / "/" { BinaryOp::Div }
+            / !percent_mode() "%" { BinaryOp::Rem }
+    }
+}
+
+const INVALID_NUMBER: &str = "valid number";
+
+/// Switches that change how the grammar reads the input.
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// Read `%` as the postfix percent operator (`50% = 0.5`) instead of
+    /// the remainder operator. The two can not be told apart in `50% - 2`,
+    /// so only one of them is available at a time.
+    pub percent: bool,
+    /// Read a number directly followed by `i`, such as `4i`, as that number
+    /// times the imaginary unit, for use with [`eval_complex`](crate::eval_complex).
+    pub complex: bool,
+}
+
+// what the grammar reads besides the input
+struct State<'a> {
+    options: &'a ParseOptions,
+    // how many brackets, calls, signs and powers the parser is inside
+    depth: Cell<usize>,
+    // where the parser first went past MAX_NESTING
+    too_deep: Cell<Option<usize>>,
+}
+
+impl State<'_> {
+    fn new(options: &ParseOptions) -> State<'_> {
+        State {
+            options,
+            depth: Cell::new(0),
+            too_deep: Cell::new(None),
+        }
+    }
+
+    fn enter(&self, position: usize) -> bool {
+        if self.depth.get() >= MAX_NESTING {
+            self.too_deep.set(self.too_deep.get().or(Some(position)));
+            return false;
+        }
+        self.depth.set(self.depth.get() + 1);
+        true
+    }
+
+    fn leave(&self) {
+        self.depth.set(self.depth.get() - 1);
+    }
+
+    // the error of a parse that failed, which is the nesting when it went too deep
+    fn error(&self, s: &str, e: peg::error::ParseError<peg::str::LineCol>) -> ParseError {
+        match self.too_deep.get() {
+            Some(position) => ParseError::TooDeep {
+                span: Span::new(position, position + 1),
+            },
+            None => convert_error(s, e),
+        }
+    }
+}
+
+fn fold_left(first: Expr, rest: Vec<(BinaryOp, Expr)>) -> Expr {
+    rest.into_iter()
+        .fold(first, |lhs, (op, rhs)| binary(op, lhs, rhs))
+}
+
+fn binary(op: BinaryOp, lhs: Expr, rhs: Expr) -> Expr {
+    let span = lhs.span().to(rhs.span());
+    Expr::Binary {
+        op,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+        span,
+    }
+}
+
+/// Parses `s` into an [`Expr`] without evaluating it.
+///
+/// Spans in the tree and in the returned error are byte offsets into `s`.
+pub fn parse(s: &str) -> Result<Expr, ParseError> {
+    parse_with(s, &ParseOptions::default())
+}
+
+/// Same as [`parse`], with non-default [`ParseOptions`].
+pub fn parse_with(s: &str, options: &ParseOptions) -> Result<Expr, ParseError> {
+    check_input(s)?;
+    let state = State::new(options);
+    let expr = expr_parser::full(s, &state).map_err(|e| state.error(s, e))?;
+    check_depth(expr)
+}
+
+/// Parses one line: an assignment such as `rate = 0.2`, an expectation such as
+/// `sqrt(16) == 4`, or an expression.
+pub fn parse_statement(s: &str) -> Result<Statement, ParseError> {
+    parse_statement_with(s, &ParseOptions::default())
+}
+
+/// Same as [`parse_statement`], with non-default [`ParseOptions`].
+pub fn parse_statement_with(s: &str, options: &ParseOptions) -> Result<Statement, ParseError> {
+    check_input(s)?;
+    let state = State::new(options);
+    let statement = expr_parser::statement(s, &state).map_err(|e| state.error(s, e))?;
+    Ok(match statement {
+        Statement::Assign { name, value, span } => Statement::Assign {
+            name,
+            value: check_depth(value)?,
+            span,
+        },
+        Statement::Expect {
+            value,
+            expected,
+            span,
+        } => Statement::Expect {
+            value: check_depth(value)?,
+            expected: check_depth(expected)?,
+            span,
+        },
+        Statement::Expr(expr) => Statement::Expr(check_depth(expr)?),
+    })
+}
+
+/// Removes a comment from one line of an expression file, returning the code before it.
+///
+/// `#` starts a comment anywhere on the line. `//` is the floor division operator, so it only
+/// starts a comment at the beginning of a line: `7 // 2` is an expression, `// 7` a comment.
+/// The code keeps its position in `line`, so spans into it are spans into `line` as well.
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::strip_comment;
+///
+/// assert_eq!(strip_comment("2 + 3 # five"), "2 + 3 ");
+/// assert_eq!(strip_comment("  // a note"), "");
+/// assert_eq!(strip_comment("7 // 2"), "7 // 2");
+/// ```
+pub fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with("//") {
+        return "";
+    }
+    match line.find('#') {
+        Some(i) => &line[..i],
+        None => line,
+    }
+}
+
+// `expr`, unless its tree is deeper than MAX_DEPTH; measured and dropped without recursion,
+// which would overflow the stack on such a tree
+fn check_depth(expr: Expr) -> Result<Expr, ParseError> {
+    let mut stack = vec![(&expr, 1)];
+    let mut deepest = 0;
+    while let Some((node, depth)) = stack.pop() {
+        deepest = deepest.max(depth);
+        stack.extend(children(node).map(|child| (child, depth + 1)));
+    }
+    if deepest <= MAX_DEPTH {
+        return Ok(expr);
+    }
+    let span = expr.span();
+    let mut stack = vec![expr];
+    while let Some(node) = stack.pop() {
+        match node {
+            Expr::Unary { operand, .. } => stack.push(*operand),
+            Expr::Binary { lhs, rhs, .. } => stack.extend([*lhs, *rhs]),
+            Expr::Call { args, .. } => stack.extend(args),
+            Expr::Group { inner, .. } => stack.push(*inner),
+            Expr::Number { .. } | Expr::Ident { .. } => {}
+        }
+    }
+    Err(ParseError::TooDeep { span })
+}
+
+fn children(expr: &Expr) -> impl Iterator<Item = &Expr> {
+    let (one, two, args): (Option<&Expr>, Option<&Expr>, &[Expr]) = match expr {
+        Expr::Unary { operand, .. } => (Some(operand), None, &[]),
+        Expr::Binary { lhs, rhs, .. } => (Some(lhs), Some(rhs), &[]),
+        Expr::Call { args, .. } => (None, None, args),
+        Expr::Group { inner, .. } => (Some(inner), None, &[]),
+        Expr::Number { .. } | Expr::Ident { .. } => (None, None, &[]),
+    };
+    one.into_iter().chain(two).chain(args)
+}
+
+fn check_input(s: &str) -> Result<(), ParseError> {
+    if s.trim().is_empty() {
+        return Err(ParseError::EmptyInput {
+            span: Span::new(0, s.len()),
+        });
+    }
+    check_balanced_brackets(s)
+}
+
+fn check_balanced_brackets(s: &str) -> Result<(), ParseError> {
+    let mut open = Vec::new();
+    for (i, c) in s.char_indices() {
+        if c == '(' {
+            open.push(i);
+        } else if c == ')' && open.pop().is_none() {
+            return Err(ParseError::UnbalancedParen {
+                span: Span::new(i, i + 1),
+            });
+        }
+    }
+    match open.pop() {
+        Some(i) => Err(ParseError::UnbalancedParen {
+            span: Span::new(i, i + 1),
+        }),
+        None => Ok(()),
+    }
+}
+
+// where the number literal ending at `end` starts, skipping back over an exponent
+// such as `e-5` and then over digits and `.`
+fn literal_start(s: &str, end: usize) -> usize {
+    let mut mantissa = &s[..end];
+    if let Some(i) = mantissa.rfind(['e', 'E']) {
+        let exponent = &mantissa[i + 1..];
+        let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+        if !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()) {
+            mantissa = &mantissa[..i];
+        }
+    }
+    mantissa
+        .rfind(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .map_or(0, |i| i + 1)
+}
+
+fn convert_error(s: &str, e: peg::error::ParseError<peg::str::LineCol>) -> ParseError {
+    let offset = e.location.offset;
+
+    if e.expected.tokens().any(|t| t == INVALID_NUMBER) {
+        // the failure is reported at the end of the literal
+        let start = literal_start(s, offset);
+        return ParseError::InvalidNumber {
+            literal: s[start..offset].to_string(),
+            span: Span::new(start, offset),
+        };
+    }
+
+    let rest = &s[offset..];
+    let len = match rest.chars().next() {
+        None => 0,
+        Some(c) if c.is_alphanumeric() || c == '_' => rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len()),
+        Some(c) => c.len_utf8(),
+    };
+
+    let mut expected: Vec<String> = e
+        .expected
+        .tokens()
+        .map(|t| match t {
+            "EOF" => "end of input".to_string(),
+            _ => match t.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+                Some(literal) => format!("'{literal}'"),
+                None => t.to_string(),
+            },
+        })
+        .collect();
+    expected.sort();
+
+    ParseError::UnexpectedToken {
+        found: rest[..len].to_string(),
+        expected,
+        span: Span::new(offset, offset + len),
+    }
+}
@@ -0,0 +1,194 @@
+//! Interactive prompt started by `cargo run` without arguments.
+
+use std::path::PathBuf;
+
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Diagnostic, Session, Statement, parse_statement,
+};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Editor, Helper};
+
+const HISTORY_FILE: &str = ".arythemetic_history";
+
+pub fn run() -> rustyline::Result<()> {
+    let mut editor: Editor<InputHelper, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(InputHelper));
+    let history = history_path();
+    if let Some(path) = &history {
+        // a missing history file just means this is the first session
+        let _ = editor.load_history(path);
+    }
+
+    println!("Enter an arithmetic expression (:help for commands, :quit to leave):");
+    let mut session = Session::new();
+    loop {
+        let input = match editor.readline("> ") {
+            Ok(input) => input,
+            // Ctrl-C drops the line being typed, Ctrl-D leaves
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e),
+        };
+        let input = input.trim();
+        if input.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(input)?;
+
+        if let Some(command) = Command::parse(input) {
+            match command {
+                Command::Quit => break,
+                Command::Vars => print_vars(&session),
+                Command::Funcs => print_funcs(&session),
+                Command::Help => print_commands(),
+                Command::Unknown(other) => println!("unknown command ':{other}', try :help"),
+            }
+            continue;
+        }
+
+        let result = parse_statement(input).and_then(|statement| {
+            let value = session.execute(&statement)?;
+            Ok((statement, value))
+        });
+        match result {
+            Ok((Statement::Assign { name, .. }, value)) => println!("{name} = {value}"),
+            Ok((_, value)) => println!("Result: {value}"),
+            Err(e) => println!("{}", Diagnostic::new(input, &e).context(session.context())),
+        }
+    }
+
+    if let Some(path) = &history {
+        editor.save_history(path)?;
+    }
+    Ok(())
+}
+
+/// A line starting with `:`, which controls the prompt instead of being evaluated.
+#[derive(Debug, PartialEq)]
+enum Command<'a> {
+    Quit,
+    Vars,
+    Funcs,
+    Help,
+    Unknown(&'a str),
+}
+
+impl<'a> Command<'a> {
+    /// `None` when `input` is not a command.
+    fn parse(input: &'a str) -> Option<Self> {
+        let command = match input.strip_prefix(':')?.trim() {
+            "quit" | "q" | "exit" => Command::Quit,
+            "vars" => Command::Vars,
+            "funcs" => Command::Funcs,
+            "help" => Command::Help,
+            other => Command::Unknown(other),
+        };
+        Some(command)
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
+
+fn print_vars(session: &Session) {
+    if session.env().is_empty() {
+        println!("no variables yet, assign one with `name = value`");
+    }
+    for (name, value) in session.env().iter() {
+        println!("{name} = {value}");
+    }
+}
+
+fn print_funcs(session: &Session) {
+    let functions = session.context().functions();
+    for name in functions.names() {
+        if let Some(function) = functions.get(name) {
+            println!("{name}: {}", function.arity());
+        }
+    }
+}
+
+fn print_commands() {
+    println!(":vars    - show assigned variables, `ans` is the last result");
+    println!(":funcs   - show available functions");
+    println!(":help    - show this list");
+    println!(":quit    - leave the prompt (also Ctrl-D)");
+}
+
+/// Keeps reading lines while a `(` is left open, so long expressions can span lines.
+struct InputHelper;
+
+impl Validator for InputHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        if is_incomplete(ctx.input()) {
+            Ok(ValidationResult::Incomplete)
+        } else {
+            Ok(ValidationResult::Valid(None))
+        }
+    }
+}
+
+// whether a `(` is still open; a surplus `)` is left to the parser to report
+fn is_incomplete(input: &str) -> bool {
+    let mut depth = 0i32;
+    for c in input.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth > 0
+}
+
+impl Completer for InputHelper {
+    type Candidate = String;
+}
+
+impl Hinter for InputHelper {
+    type Hint = String;
+}
+
+impl Highlighter for InputHelper {}
+
+impl Helper for InputHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_brackets_continue_the_input() {
+        assert!(is_incomplete("(1 + 2"));
+        assert!(is_incomplete("max(1, (2"));
+        assert!(is_incomplete("(1 +\n (2 * 3)"));
+        assert!(!is_incomplete("(1 +\n (2 * 3))"));
+        assert!(!is_incomplete("1 + 2"));
+        assert!(!is_incomplete(""));
+        assert!(!is_incomplete("1 + 2)"));
+        // a surplus `)` cancels a later `(`, and the line goes to the parser, which reports it
+        assert!(!is_incomplete(") + ("));
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(Command::parse(":quit"), Some(Command::Quit));
+        assert_eq!(Command::parse(":q"), Some(Command::Quit));
+        assert_eq!(Command::parse(":exit"), Some(Command::Quit));
+        assert_eq!(Command::parse(": vars "), Some(Command::Vars));
+        assert_eq!(Command::parse(":funcs"), Some(Command::Funcs));
+        assert_eq!(Command::parse(":help"), Some(Command::Help));
+        assert_eq!(
+            Command::parse(":frobnicate"),
+            Some(Command::Unknown("frobnicate"))
+        );
+        assert_eq!(Command::parse("1 + 2"), None);
+        assert_eq!(Command::parse("quit"), None);
+    }
+}
@@ -0,0 +1,142 @@
+//! State carried from one line of input to the next.
+
+use crate::ast::Statement;
+use crate::{Context, Environment, ParseError, ParseOptions, parse_statement_with};
+
+/// Names under which the result of the previous line is available.
+const PREVIOUS_RESULT: [&str; 2] = ["ans", "_"];
+
+/// Allowed difference between the two sides of `value == expected` unless
+/// [`Session::tolerance`] sets another one.
+pub const DEFAULT_TOLERANCE: f64 = 1e-9;
+
+/// Evaluates lines one after another, keeping the variables they assign.
+///
+/// `name = value` stores a variable for the following lines, and the result of
+/// every successful line, assignments included, is available as `ans` or `_`.
+/// A line that fails leaves the session unchanged, and so does `value == expected`
+/// when the two sides differ by more than the session's tolerance, which fails
+/// with [`ParseError::ExpectationFailed`].
+///
+/// ```
+/// use arythemetic_expressions_parser_kharchenko_kma::Session;
+///
+/// let mut session = Session::new();
+/// session.run("rate = 0.2").unwrap();
+/// assert_eq!(session.run("100 * (1 + rate)").unwrap(), 120.0);
+/// assert_eq!(session.run("ans / 2").unwrap(), 60.0);
+/// assert_eq!(session.run("0.1 + 0.2 == 0.3").unwrap(), 0.1 + 0.2);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Session {
+    context: Context,
+    env: Environment,
+    options: ParseOptions,
+    tolerance: f64,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Session {
+            context: Context::default(),
+            env: Environment::default(),
+            options: ParseOptions::default(),
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Session::default()
+    }
+
+    /// A session that resolves constants and functions through `context`.
+    pub fn with_context(context: Context) -> Self {
+        Session {
+            context,
+            ..Session::default()
+        }
+    }
+
+    /// Sets the [`ParseOptions`] used by [`Session::run`].
+    pub fn options(mut self, options: ParseOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Sets how far apart the two sides of `value == expected` may be: absolutely for
+    /// expected values up to 1 in magnitude, relative to the expected value above that.
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Whether `actual` is `expected` within the session's tolerance; `NaN` matches `NaN`.
+    pub fn matches(&self, actual: f64, expected: f64) -> bool {
+        actual == expected
+            || (actual.is_nan() && expected.is_nan())
+            || (actual - expected).abs() <= self.tolerance * expected.abs().max(1.0)
+    }
+
+    /// Parses and executes one line, returning its value.
+    pub fn run(&mut self, line: &str) -> Result<f64, ParseError> {
+        let statement = parse_statement_with(line, &self.options)?;
+        self.execute(&statement)
+    }
+
+    /// Executes an already parsed statement, returning its value.
+    pub fn execute(&mut self, statement: &Statement) -> Result<f64, ParseError> {
+        let value = match statement {
+            Statement::Assign { name, value, .. } => {
+                let value = self.context.eval_with(value, &self.env)?;
+                self.env.set(name.as_str(), value);
+                value
+            }
+            Statement::Expect {
+                value,
+                expected,
+                span,
+            } => {
+                // both sides see the `ans` of the previous line
+                let actual = self.context.eval_with(value, &self.env)?;
+                let expected = self.context.eval_with(expected, &self.env)?;
+                if !self.matches(actual, expected) {
+                    return Err(ParseError::ExpectationFailed {
+                        actual,
+                        expected,
+                        span: *span,
+                    });
+                }
+                actual
+            }
+            Statement::Expr(value) => self.context.eval_with(value, &self.env)?,
+        };
+        for name in PREVIOUS_RESULT {
+            self.env.set(name, value);
+        }
+        Ok(value)
+    }
+
+    /// Result of the last successful line, if there was one.
+    pub fn ans(&self) -> Option<f64> {
+        self.env.get(PREVIOUS_RESULT[0])
+    }
+
+    /// Variables assigned so far, including `ans` and `_`.
+    pub fn env(&self) -> &Environment {
+        &self.env
+    }
+
+    pub fn env_mut(&mut self) -> &mut Environment {
+        &mut self.env
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context {
+        &mut self.context
+    }
+}
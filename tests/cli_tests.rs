@@ -0,0 +1,264 @@
+use anyhow::Result;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn cli(args: &[&str], stdin: impl AsRef<[u8]>) -> Result<Output> {
+    let mut child = Command::new(env!(
+        "CARGO_BIN_EXE_arythemetic_expressions_parser_kharchenko_kma"
+    ))
+    .args(args)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()?;
+    child.stdin.take().unwrap().write_all(stdin.as_ref())?;
+    Ok(child.wait_with_output()?)
+}
+
+#[test]
+fn test_eval_prints_only_the_value() -> Result<()> {
+    let out = cli(&["eval", "2 + 3 * 4"], "")?;
+    assert!(out.status.success());
+    assert_eq!(String::from_utf8(out.stdout)?, "14\n");
+    Ok(())
+}
+
+#[test]
+fn test_eval_failure() -> Result<()> {
+    let out = cli(&["eval", "1 / 0"], "")?;
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+    assert!(String::from_utf8(out.stderr)?.contains("error[E003]"));
+    Ok(())
+}
+
+#[test]
+fn test_parse_stdin() -> Result<()> {
+    let out = cli(&["parse", "-"], "rate = 0.5\n\n10 * rate\n")?;
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout)?,
+        "Line 1: rate = 0.5\nLine 3: 10 * rate = 5\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_stdin_with_failing_line() -> Result<()> {
+    let out = cli(&["parse", "-"], "1 + 1\nsqrt(-1)\n2 + 2\n")?;
+    assert!(!out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout)?,
+        "Line 1: 1 + 1 = 2\nLine 3: 2 + 2 = 4\n"
+    );
+    assert!(String::from_utf8(out.stderr)?.contains(" --> <stdin>:2:1"));
+    Ok(())
+}
+
+#[test]
+fn test_parse_fails_on_a_wrong_expectation() -> Result<()> {
+    let out = cli(&["parse", "-"], "2 + 3 * 4 == 14\n2 + 3 * 4 == 15\n")?;
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout)?,
+        "Line 1: 2 + 3 * 4 == 14 = 14\n"
+    );
+    assert!(String::from_utf8(out.stderr)?.contains("error[E013]"));
+
+    let out = cli(&["eval", "sqrt(16) == 5"], "")?;
+    assert!(!out.status.success());
+    assert!(out.stdout.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_parse_and_check_agree_on_expectations() -> Result<()> {
+    let input = "0.1 + 0.2 == 0.3\nsqrt(2) * sqrt(2) == 2\n";
+    assert_eq!(cli(&["parse", "-"], input)?.status.code(), Some(0));
+    assert_eq!(cli(&["check", "-"], input)?.status.code(), Some(0));
+    assert_eq!(
+        cli(&["eval", "0.1 + 0.2 == 0.3"], "")?.status.code(),
+        Some(0)
+    );
+
+    let input = "pi == 3.1416\n";
+    assert_eq!(cli(&["parse", "-"], input)?.status.code(), Some(1));
+    let out = cli(&["check", "-"], input)?;
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8(out.stdout)?.contains("expected 3.1416"));
+    Ok(())
+}
+
+#[test]
+fn test_parse_survives_deep_nesting() -> Result<()> {
+    let deep = format!("{}1{}", "(".repeat(50_000), ")".repeat(50_000));
+    let out = cli(&["parse", "-"], format!("1 + 1\n{deep}\n2 + 2\n"))?;
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout)?,
+        "Line 1: 1 + 1 = 2\nLine 3: 2 + 2 = 4\n"
+    );
+    assert!(String::from_utf8(out.stderr)?.contains("error[E014]"));
+    Ok(())
+}
+
+#[test]
+fn test_format_ndjson() -> Result<()> {
+    let out = cli(&["parse", "--format", "ndjson", "-"], "x = 2\nx / 0\n")?;
+    assert!(!out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout)?,
+        concat!(
+            r#"{"line":1,"source":"x = 2","variable":"x","value":2,"error":null}"#,
+            "\n",
+            r#"{"line":2,"source":"x / 0","variable":null,"value":null,"error":{"code":"E003","kind":"DivisionByZero","message":"division by zero at 4..5","span":{"start":4,"end":5}}}"#,
+            "\n"
+        )
+    );
+    assert!(out.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_format_json() -> Result<()> {
+    let out = cli(&["eval", "--format=json", "\"a\\b\""], "")?;
+    assert!(!out.status.success());
+    let stdout = String::from_utf8(out.stdout)?;
+    assert!(stdout.starts_with("[\n  {\"line\":1,\"source\":\"\\\"a\\\\b\\\"\""));
+    assert!(stdout.ends_with("}\n]\n"));
+
+    let out = cli(&["parse", "--format", "json", "-"], "")?;
+    assert_eq!(String::from_utf8(out.stdout)?, "[]\n");
+    Ok(())
+}
+
+#[test]
+fn test_format_csv() -> Result<()> {
+    let out = cli(&["parse", "--format", "csv", "-"], "max(1, 2)\nfoo\n")?;
+    assert_eq!(
+        String::from_utf8(out.stdout)?,
+        "line,source,variable,value,error_code,error_kind,error_message,span_start,span_end\n\
+         1,\"max(1, 2)\",,2,,,,,\n\
+         2,foo,,,E008,UnknownVariable,unknown variable 'foo' at 0..3,0,3\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unknown_format() -> Result<()> {
+    let out = cli(&["eval", "--format", "xml", "1"], "")?;
+    assert!(!out.status.success());
+    assert!(String::from_utf8(out.stderr)?.contains("unknown format 'xml'"));
+    Ok(())
+}
+
+#[test]
+fn test_exit_codes() -> Result<()> {
+    assert_eq!(cli(&["parse", "-"], "1 + 1\n")?.status.code(), Some(0));
+    assert_eq!(
+        cli(&["parse", "-"], "1 + 1\n1 / 0\n")?.status.code(),
+        Some(1)
+    );
+    assert_eq!(cli(&["eval", "log(0)"], "")?.status.code(), Some(1));
+    assert_eq!(
+        cli(&["parse", "no/such/file.txt"], "")?.status.code(),
+        Some(2)
+    );
+    assert_eq!(cli(&["parse"], "")?.status.code(), Some(2));
+    assert_eq!(
+        cli(&["eval", "--format", "xml", "1"], "")?.status.code(),
+        Some(2)
+    );
+    assert_eq!(cli(&["frobnicate"], "")?.status.code(), Some(2));
+    Ok(())
+}
+
+#[test]
+fn test_strict_stops_at_first_failure() -> Result<()> {
+    let out = cli(
+        &["parse", "--strict", "--format", "csv", "-"],
+        "1\n2 +\n3\n",
+    )?;
+    assert_eq!(out.status.code(), Some(1));
+    let stdout = String::from_utf8(out.stdout)?;
+    assert_eq!(stdout.lines().count(), 3);
+    assert!(!stdout.contains("\n3,"));
+    Ok(())
+}
+
+#[test]
+fn test_invalid_utf8_is_a_failed_line() -> Result<()> {
+    let out = cli(
+        &["parse", "--format", "ndjson", "-"],
+        b"1 + \xff\n2 * 2\r\n",
+    )?;
+    assert_eq!(out.status.code(), Some(1));
+    let stdout = String::from_utf8(out.stdout)?;
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains(r#""kind":"UnexpectedToken""#));
+    assert!(lines[0].contains(r#""span":{"start":4,"end":7}"#));
+    assert!(lines[1].contains(r#""source":"2 * 2","variable":null,"value":4"#));
+    Ok(())
+}
+
+#[test]
+fn test_comments_are_ignored() -> Result<()> {
+    let input = "# fixture header\n// another note\n7 // 2 # floor division\nrate = 0.5 # half\n";
+    let out = cli(&["parse", "-"], input)?;
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8(out.stdout)?,
+        "Line 3: 7 // 2 = 3\nLine 4: rate = 0.5\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_check_reports_pass_and_fail() -> Result<()> {
+    let input = "# suite\nsqrt(16) == 4\n2+3*4 # => 14\nx = 3\nx^2 # => 10\n";
+    let out = cli(&["check", "-"], input)?;
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(out.stdout)?,
+        "PASS <stdin>:2: sqrt(16) == 4\n\
+         PASS <stdin>:3: 2+3*4 # => 14\n\
+         FAIL <stdin>:5: x^2 = 9, expected 10\n\
+         2 passed, 1 failed\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_check_tolerance() -> Result<()> {
+    let input = "pi # => 3.1416\n";
+    assert_eq!(cli(&["check", "-"], input)?.status.code(), Some(1));
+    let out = cli(&["check", "--tolerance", "1e-4", "-"], input)?;
+    assert_eq!(out.status.code(), Some(0));
+    assert!(String::from_utf8(out.stdout)?.ends_with("1 passed, 0 failed\n"));
+    assert_eq!(
+        cli(&["check", "--tolerance", "-1", "-"], input)?
+            .status
+            .code(),
+        Some(2)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_check_errors_point_into_the_line() -> Result<()> {
+    let out = cli(&["check", "-"], "1 # => foo\nlog(0) == 1\n")?;
+    assert_eq!(out.status.code(), Some(1));
+    let stdout = String::from_utf8(out.stdout)?;
+    assert!(stdout.contains(" --> <stdin>:1:8"));
+    assert!(stdout.contains(" --> <stdin>:2:1"));
+    assert!(stdout.ends_with("0 passed, 2 failed\n"));
+    Ok(())
+}
+
+#[test]
+fn test_check_expressions_file() -> Result<()> {
+    let out = cli(&["check", "expressions.txt"], "")?;
+    assert!(out.status.success(), "{}", String::from_utf8(out.stdout)?);
+    Ok(())
+}
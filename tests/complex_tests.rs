@@ -0,0 +1,204 @@
+use std::f64::consts::PI;
+
+use anyhow::{Result, anyhow};
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Arity, BinaryOp, Complex, Context, Environment, Expr, ParseError, ParseOptions, Span,
+    eval_complex, parse, parse_with,
+};
+
+fn complex(expr: &str) -> Result<Complex> {
+    let options = ParseOptions {
+        complex: true,
+        ..ParseOptions::default()
+    };
+    Ok(eval_complex(&parse_with(expr, &options)?)?)
+}
+
+fn assert_close(actual: Complex, expected: Complex) {
+    assert!(
+        (actual.re - expected.re).abs() < 1e-12 && (actual.im - expected.im).abs() < 1e-12,
+        "{actual} is not {expected}"
+    );
+}
+
+#[test]
+fn test_imaginary_unit() -> Result<()> {
+    assert_eq!(complex("i")?, Complex::I);
+    assert_eq!(complex("i^2")?, Complex::new(-1.0, 0.0));
+    assert_eq!(complex("sqrt(-1)")?, Complex::I);
+    assert_eq!(complex("sqrt(-4)")?, Complex::new(0.0, 2.0));
+    assert_eq!(complex("log(-1)")?, Complex::new(0.0, PI));
+    assert_eq!(complex("ln(-e)")?, Complex::new(1.0, PI));
+    Ok(())
+}
+
+#[test]
+fn test_imaginary_literal() -> Result<()> {
+    let options = ParseOptions {
+        complex: true,
+        ..ParseOptions::default()
+    };
+    match parse_with("4i", &options)? {
+        Expr::Binary {
+            op: BinaryOp::Mul,
+            lhs,
+            rhs,
+            span,
+        } => {
+            assert!(matches!(*lhs, Expr::Number { value: 4.0, .. }));
+            assert!(matches!(*rhs, Expr::Ident { ref name, .. } if name == "i"));
+            assert_eq!(rhs.span(), Span::new(1, 2));
+            assert_eq!(span, Span::new(0, 2));
+        }
+        other => return Err(anyhow!("Expected 4 * i, got {other:?}")),
+    }
+    // `4in` is a number followed by a name, and `4i` needs the option
+    match parse_with("4in", &options) {
+        Ok(_) => return Err(anyhow!("Expected UnexpectedToken error")),
+        Err(e) => assert!(matches!(e, ParseError::UnexpectedToken { .. })),
+    }
+    match parse("4i") {
+        Ok(_) => return Err(anyhow!("Expected UnexpectedToken error")),
+        Err(e) => assert!(matches!(e, ParseError::UnexpectedToken { .. })),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_complex_arithmetic() -> Result<()> {
+    assert_eq!(complex("(1 + 2i) + (3 - 4i)")?, Complex::new(4.0, -2.0));
+    assert_eq!(complex("(1 + 2i) * (3 - i)")?, Complex::new(5.0, 5.0));
+    assert_eq!(complex("(3 + 4i) / (1 - 2i)")?, Complex::new(-1.0, 2.0));
+    assert_eq!(complex("-(2 - 3i)")?, Complex::new(-2.0, 3.0));
+    assert_eq!(complex("(1 + i)^2")?, Complex::new(0.0, 2.0));
+    assert_eq!(complex("(1 + i)^-1")?, Complex::new(0.5, -0.5));
+    assert_close(complex("i^i")?, Complex::new((-PI / 2.0).exp(), 0.0));
+    assert_close(complex("(-8)^(1/3)")?, Complex::new(1.0, 3f64.sqrt()));
+    // real expressions evaluate exactly as in f64
+    assert_eq!(complex("0.1 + 0.2")?, Complex::new(0.1 + 0.2, 0.0));
+    assert_eq!(
+        complex("7 % 3 + 2^0.5")?,
+        Complex::new(1.0 + 2f64.sqrt(), 0.0)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_complex_functions() -> Result<()> {
+    assert_close(complex("exp(i * pi)")?, Complex::new(-1.0, 0.0));
+    assert_eq!(complex("abs(3 + 4i)")?, Complex::new(5.0, 0.0));
+    assert_eq!(complex("re(3 - 4i) + im(3 - 4i)")?, Complex::new(-1.0, 0.0));
+    assert_eq!(complex("conj(3 - 4i)")?, Complex::new(3.0, 4.0));
+    assert_close(complex("arg(-1 + 0i)")?, Complex::new(PI, 0.0));
+    assert_close(complex("sqrt(-3 + 4i)")?, Complex::new(1.0, 2.0));
+    assert_close(complex("cos(i)")?, Complex::new(1f64.cosh(), 0.0));
+    assert_close(complex("log10(-100)")?, Complex::new(2.0, PI / 10f64.ln()));
+    assert_close(complex("pow(-4, 0.5)")?, Complex::new(0.0, 2.0));
+    assert_close(complex("sign(3i)")?, Complex::I);
+    Ok(())
+}
+
+#[test]
+fn test_complex_display() -> Result<()> {
+    assert_eq!(complex("3 + 4i")?.to_string(), "3 + 4i");
+    assert_eq!(complex("3 - 4i")?.to_string(), "3 - 4i");
+    assert_eq!(complex("-2.5")?.to_string(), "-2.5 + 0i");
+    assert_eq!(complex("-i")?.to_string(), "0 - 1i");
+    assert_eq!(Complex::new(-0.0, -0.0).to_string(), "0 + 0i");
+    Ok(())
+}
+
+#[test]
+fn test_complex_errors() -> Result<()> {
+    for (expr, operation, span) in [
+        ("floor(2 + i)", "floor", Span::new(0, 12)),
+        ("i % 2", "%", Span::new(0, 5)),
+        ("(1 + i)!", "factorial", Span::new(0, 8)),
+        ("max(1, i)", "max", Span::new(0, 9)),
+    ] {
+        match complex(expr) {
+            Ok(value) => return Err(anyhow!("Expected NotReal error, got {value}")),
+            Err(e) => assert_eq!(
+                e.downcast::<ParseError>()?,
+                ParseError::NotReal {
+                    operation: operation.to_string(),
+                    span,
+                }
+            ),
+        }
+    }
+    match complex("i / (i - i)") {
+        Ok(_) => return Err(anyhow!("Expected DivisionByZero error")),
+        Err(e) => assert!(matches!(
+            e.downcast::<ParseError>()?,
+            ParseError::DivisionByZero { .. }
+        )),
+    }
+    for expr in ["log(0)", "tan(pi / 2)", "sqrt(nan)"] {
+        match complex(expr) {
+            Ok(value) => return Err(anyhow!("Expected DomainError error, got {value}")),
+            Err(e) => assert!(matches!(
+                e.downcast::<ParseError>()?,
+                ParseError::DomainError { .. }
+            )),
+        }
+    }
+    match complex("re(1, 2)") {
+        Ok(_) => return Err(anyhow!("Expected ArityMismatch error")),
+        Err(e) => assert!(matches!(
+            e.downcast::<ParseError>()?,
+            ParseError::ArityMismatch { .. }
+        )),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_replaced_builtins_are_called() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.functions_mut()
+        .register("sqrt", Arity::Exact(1), |args| Ok(args[0].abs().sqrt()));
+    ctx.functions_mut()
+        .register("re", Arity::Exact(1), |args| Ok(args[0] * 10.0));
+    let env = Environment::new();
+
+    assert_eq!(
+        ctx.eval_complex(&parse("sqrt(-4)")?, &env)?,
+        Complex::new(2.0, 0.0)
+    );
+    assert_eq!(
+        ctx.eval_complex(&parse("re(2)")?, &env)?,
+        Complex::new(20.0, 0.0)
+    );
+    // the built-in names that are not replaced keep their complex meaning
+    assert_eq!(
+        ctx.eval_complex(&parse("im(3 - 4*i)")?, &env)?,
+        Complex::from(-4.0)
+    );
+    for (expr, operation) in [("sqrt(i)", "sqrt"), ("re(1 + i)", "re")] {
+        match ctx.eval_complex(&parse(expr)?, &env) {
+            Ok(value) => return Err(anyhow!("Expected NotReal error, got {value}")),
+            Err(e) => assert!(
+                matches!(e, ParseError::NotReal { operation: ref op, .. } if op == operation)
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_complex_with_variables() -> Result<()> {
+    let ctx = Context::default();
+    let env: Environment = [("x", -9.0)].into_iter().collect();
+    let value = ctx.eval_complex(&parse("sqrt(x) + 1")?, &env)?;
+    assert_eq!(value, Complex::new(1.0, 3.0));
+    assert!(!value.is_real());
+    assert_eq!(value.abs(), 10f64.sqrt());
+
+    // a variable called `i` hides the imaginary unit
+    let env: Environment = [("i", 2.0)].into_iter().collect();
+    let value = ctx.eval_complex(&parse("i^2")?, &env)?;
+    assert_eq!(value, Complex::from(4.0));
+    assert!(value.is_real());
+    Ok(())
+}
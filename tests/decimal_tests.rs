@@ -0,0 +1,134 @@
+use anyhow::{Result, anyhow};
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Context, DecimalOptions, Environment, ParseError, Rounding, Span, eval_decimal, parse,
+};
+
+fn decimal(expr: &str, scale: u32, rounding: Rounding) -> Result<String> {
+    let options = DecimalOptions::new(scale, rounding);
+    Ok(eval_decimal(&parse(expr)?, &options)?.to_string())
+}
+
+#[test]
+fn test_base_ten_literals() -> Result<()> {
+    assert_eq!(decimal("0.1 + 0.2", 2, Rounding::HalfEven)?, "0.30");
+    assert_eq!(decimal("1.10 - 1", 4, Rounding::HalfEven)?, "0.1000");
+    assert_eq!(decimal("19.99 * 3", 2, Rounding::HalfEven)?, "59.97");
+    assert_eq!(decimal("7", 0, Rounding::HalfEven)?, "7");
+    assert_eq!(decimal("-0.05", 2, Rounding::HalfEven)?, "-0.05");
+    Ok(())
+}
+
+#[test]
+fn test_rounding_modes() -> Result<()> {
+    for (expr, half_even, half_up, down) in [
+        ("2.345 * 1", "2.34", "2.35", "2.34"),
+        ("2.355 * 1", "2.36", "2.36", "2.35"),
+        ("-2.345 * 1", "-2.34", "-2.35", "-2.34"),
+        ("2.349 * 1", "2.35", "2.35", "2.34"),
+        ("-2.349 * 1", "-2.35", "-2.35", "-2.34"),
+    ] {
+        assert_eq!(decimal(expr, 2, Rounding::HalfEven)?, half_even, "{expr}");
+        assert_eq!(decimal(expr, 2, Rounding::HalfUp)?, half_up, "{expr}");
+        assert_eq!(decimal(expr, 2, Rounding::Down)?, down, "{expr}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_literals_are_rounded_only_in_results() -> Result<()> {
+    assert_eq!(decimal("10000 * 0.0125", 2, Rounding::HalfEven)?, "125.00");
+    assert_eq!(decimal("1000 * 0.005", 2, Rounding::HalfEven)?, "5.00");
+    assert_eq!(decimal("-0.0125 * 10000", 2, Rounding::Down)?, "-125.00");
+    assert_eq!(decimal("0.125", 2, Rounding::HalfEven)?, "0.12");
+    assert_eq!(decimal("-(0.125)", 2, Rounding::HalfUp)?, "-0.13");
+
+    let options = DecimalOptions::new(2, Rounding::HalfEven);
+    let env: Environment = [("rate", 0.0125)].into_iter().collect();
+    let value = Context::default().eval_decimal(&parse("10000 * rate")?, &env, &options)?;
+    assert_eq!(value.to_string(), "125.00");
+    Ok(())
+}
+
+#[test]
+fn test_division_rounded_at_scale() -> Result<()> {
+    assert_eq!(decimal("2 / 3", 2, Rounding::HalfUp)?, "0.67");
+    assert_eq!(decimal("2 / 3", 2, Rounding::Down)?, "0.66");
+    assert_eq!(decimal("-2 / 3", 2, Rounding::Down)?, "-0.66");
+    assert_eq!(
+        decimal("1 / 7", 30, Rounding::HalfEven)?,
+        "0.142857142857142857142857142857"
+    );
+    // every intermediate result is rounded, as on a calculator
+    assert_eq!(decimal("10 / 3 * 3", 2, Rounding::HalfEven)?, "9.99");
+    assert_eq!(decimal("1 / 8", 2, Rounding::HalfEven)?, "0.12");
+    assert_eq!(decimal("3 / 8", 2, Rounding::HalfEven)?, "0.38");
+    Ok(())
+}
+
+#[test]
+fn test_decimal_functions() -> Result<()> {
+    assert_eq!(
+        decimal("sqrt(2)", 20, Rounding::HalfEven)?,
+        "1.41421356237309504880"
+    );
+    assert_eq!(decimal("sqrt(2)", 3, Rounding::Down)?, "1.414");
+    assert_eq!(decimal("sqrt(2.25)", 3, Rounding::HalfEven)?, "1.500");
+    assert_eq!(
+        decimal("round(2.5) + abs(-1.25)", 2, Rounding::HalfEven)?,
+        "4.25"
+    );
+    assert_eq!(decimal("1.05^2", 2, Rounding::HalfUp)?, "1.10");
+    assert_eq!(decimal("200 * 0.05", 2, Rounding::HalfEven)?, "10.00");
+    // functions without a decimal implementation are rounded from f64
+    assert_eq!(decimal("2 * pi", 4, Rounding::HalfEven)?, "6.2832");
+    assert_eq!(decimal("ln(e)", 6, Rounding::HalfEven)?, "1.000000");
+    Ok(())
+}
+
+#[test]
+fn test_decimal_errors() -> Result<()> {
+    let options = DecimalOptions::new(2, Rounding::HalfEven);
+    match eval_decimal(&parse("1 / (0.1 + 0.2 - 0.3)")?, &options) {
+        Ok(_) => return Err(anyhow!("Expected DivisionByZero error")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::DivisionByZero {
+                span: Span::new(4, 21)
+            }
+        ),
+    }
+    match eval_decimal(&parse("sqrt(-2)")?, &options) {
+        Ok(_) => return Err(anyhow!("Expected DomainError error")),
+        Err(e) => assert!(matches!(e, ParseError::DomainError { .. })),
+    }
+    match eval_decimal(&parse("1 + inf")?, &options) {
+        Ok(_) => return Err(anyhow!("Expected NotExact error")),
+        Err(e) => assert!(matches!(e, ParseError::NotExact { .. })),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_huge_powers_have_no_decimal_value() -> Result<()> {
+    let options = DecimalOptions::new(2, Rounding::HalfEven);
+    match eval_decimal(&parse("(2^100000)^100000")?, &options) {
+        Ok(value) => return Err(anyhow!("Expected NotExact error, got {value}")),
+        Err(e) => assert!(matches!(e, ParseError::NotExact { .. })),
+    }
+    assert_eq!(decimal("0.5^1000000000", 2, Rounding::HalfEven)?, "0.00");
+    Ok(())
+}
+
+#[test]
+fn test_decimal_with_variables() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.set_constant("vat", 0.2);
+    let env: Environment = [("price", 19.99)].into_iter().collect();
+    let options = DecimalOptions::new(2, Rounding::HalfUp);
+    let value = ctx.eval_decimal(&parse("price * (1 + vat)")?, &env, &options)?;
+    assert_eq!(value.to_string(), "23.99");
+    assert_eq!(value.unscaled().to_string(), "2399");
+    assert_eq!(value.scale(), 2);
+    assert_eq!(value.to_f64(), 23.99);
+    Ok(())
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Arity, Context, Diagnostic, EvalError, parse, parse_expression, render_diagnostic,
+};
+
+#[test]
+fn test_render_unknown_function() -> Result<()> {
+    let src = "2 + sqr(4)";
+    let e = parse_expression(src).unwrap_err();
+    assert_eq!(
+        render_diagnostic(src, &e),
+        "error[E005]: unknown function 'sqr' at 4..10\n \
+         --> 1:5\n  \
+         |\n\
+         1 | 2 + sqr(4)\n  \
+         |     ^^^^^^ not a known function\n  \
+         = hint: did you mean 'sqrt'?"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_render_with_origin_and_line() -> Result<()> {
+    let src = "1 / (2 - 2)";
+    let e = parse_expression(src).unwrap_err();
+    let out = Diagnostic::new(src, &e)
+        .origin("expressions.txt")
+        .first_line(12)
+        .to_string();
+    assert_eq!(
+        out,
+        "error[E003]: division by zero at 4..11\n  \
+         --> expressions.txt:12:5\n   \
+         |\n\
+         12 | 1 / (2 - 2)\n   \
+         |     ^^^^^^^ this evaluates to zero"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_render_multiline_source() -> Result<()> {
+    let src = "1 +\n(2 * 3";
+    let e = parse_expression(src).unwrap_err();
+    let out = render_diagnostic(src, &e);
+    assert!(out.contains("2 | (2 * 3\n  | ^ this bracket is never closed"));
+    assert!(out.ends_with("= hint: add a matching ')'"));
+    Ok(())
+}
+
+#[test]
+fn test_render_end_of_input() -> Result<()> {
+    let src = "1 +";
+    let e = parse_expression(src).unwrap_err();
+    let out = render_diagnostic(src, &e);
+    assert!(out.starts_with("error[E001]: unexpected end of input"));
+    assert!(out.contains("1 | 1 +\n  |    ^ unexpected here"));
+    Ok(())
+}
+
+#[test]
+fn test_hint_domain() -> Result<()> {
+    let src = "sqrt(-4)";
+    let e = parse_expression(src).unwrap_err();
+    assert_eq!(
+        Diagnostic::new(src, &e).hint().as_deref(),
+        Some("sqrt is only defined for x >= 0")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_hint_unknown_constant() -> Result<()> {
+    let src = "2 * tua";
+    let e = parse_expression(src).unwrap_err();
+    let out = render_diagnostic(src, &e);
+    assert!(out.starts_with("error[E008]: unknown variable 'tua' at 4..7"));
+    assert!(out.ends_with("= hint: did you mean 'tau'?"));
+    Ok(())
+}
+
+#[test]
+fn test_hint_missing_operator() -> Result<()> {
+    let src = "2 pi";
+    let e = parse_expression(src).unwrap_err();
+    assert_eq!(
+        Diagnostic::new(src, &e).hint().as_deref(),
+        Some("missing an operator before 'pi'?")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_hints_use_the_context() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.functions_mut()
+        .register("tax", Arity::Exact(1), |args| Ok(args[0] * 0.2));
+    ctx.functions_mut()
+        .register("sqrt", Arity::Exact(1), |args| {
+            if args[0] < -100.0 {
+                return Err(EvalError::Domain { arg: args[0] });
+            }
+            Ok(args[0].abs().sqrt())
+        });
+    ctx.set_constant("vat", 0.2);
+
+    let src = "100 + txa(100)";
+    let e = ctx.eval(&parse(src)?).unwrap_err();
+    let hint = Diagnostic::new(src, &e).context(&ctx).hint();
+    assert_eq!(hint.as_deref(), Some("did you mean 'tax'?"));
+    // without the context only built-in functions are known
+    let hint = Diagnostic::new(src, &e).hint();
+    assert_ne!(hint.as_deref(), Some("did you mean 'tax'?"));
+
+    let src = "100 * vta";
+    let e = ctx.eval(&parse(src)?).unwrap_err();
+    let hint = Diagnostic::new(src, &e).context(&ctx).hint();
+    assert_eq!(hint.as_deref(), Some("did you mean 'vat'?"));
+
+    // the replaced sqrt does not have the built-in domain
+    let src = "sqrt(-200)";
+    let e = ctx.eval(&parse(src)?).unwrap_err();
+    assert_eq!(Diagnostic::new(src, &e).context(&ctx).hint(), None);
+    let e = parse_expression(src).unwrap_err();
+    let hint = Diagnostic::new(src, &e).context(&Context::default()).hint();
+    assert_eq!(hint.as_deref(), Some("sqrt is only defined for x >= 0"));
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use anyhow::{Result, anyhow};
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Arity, Context, DecimalOptions, Environment, Exact, Inexact, Integer, ParseError, Rounding,
+    Span, eval_exact, parse,
+};
+
+fn exact(expr: &str) -> Result<String> {
+    Ok(eval_exact(&parse(expr)?, Inexact::Error)?.to_string())
+}
+
+#[test]
+fn test_no_float_noise() -> Result<()> {
+    assert_eq!(exact("0.1 + 0.2")?, "3/10");
+    assert_eq!(exact("1/3*3")?, "1");
+    assert_eq!(exact("1/3")?, "1/3");
+    assert_eq!(exact("-2/6")?, "-1/3");
+    assert_eq!(exact("1.10 - 1")?, "1/10");
+    Ok(())
+}
+
+#[test]
+fn test_scientific_notation() -> Result<()> {
+    assert_eq!(exact("1e5 + 1")?, "100001");
+    assert_eq!(exact("1e-5")?, "1/100000");
+    assert_eq!(exact("1.5E+2 * 2")?, "300");
+    assert_eq!(exact("2.5e-1 + 0.75")?, "1");
+    // an exponent too large to expand continues in f64
+    let value = eval_exact(&parse("1e999999 * 2")?, Inexact::Float)?;
+    assert_eq!(value, Exact::Float(f64::INFINITY));
+    match eval_exact(&parse("1e999999")?, Inexact::Error) {
+        Ok(value) => return Err(anyhow!("Expected NotExact error, got {value}")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::NotExact {
+                operation: "1e999999".to_string(),
+                span: Span::new(0, 8),
+            }
+        ),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_exact_operators() -> Result<()> {
+    assert_eq!(exact("7 % 3")?, "1");
+    assert_eq!(exact("-7 // 2")?, "-4");
+    assert_eq!(exact("7.5 % 2")?, "3/2");
+    assert_eq!(exact("(2/3)^3")?, "8/27");
+    assert_eq!(exact("2^-2")?, "1/4");
+    assert_eq!(exact("2^100")?, "1267650600228229401496703205376");
+    assert_eq!(exact("25!")?, "15511210043330985984000000");
+    assert_eq!(exact("(9/4)^0.5")?, "3/2");
+    assert_eq!(exact("-8^(1/3)")?, "-2");
+    Ok(())
+}
+
+#[test]
+fn test_exact_functions() -> Result<()> {
+    assert_eq!(exact("sqrt(16/9)")?, "4/3");
+    assert_eq!(exact("cbrt(-27)")?, "-3");
+    assert_eq!(exact("abs(-1/3) + max(1/2, 2/3, 1/6)")?, "1");
+    assert_eq!(
+        exact("round(5/2) + floor(-1/2) + ceil(1/3) + trunc(-5/3)")?,
+        "2"
+    );
+    assert_eq!(exact("clamp(7/2, 0, 3)")?, "3");
+    assert_eq!(exact("pow(4, 3/2)")?, "8");
+    Ok(())
+}
+
+#[test]
+fn test_irrational_is_an_error() -> Result<()> {
+    for (expr, operation, span) in [
+        ("1 + sqrt(2)", "sqrt", Span::new(4, 11)),
+        ("log(3)", "log", Span::new(0, 6)),
+        ("2 * pi", "pi", Span::new(4, 6)),
+        ("2^0.5", "pow", Span::new(0, 5)),
+        ("0.5!", "factorial", Span::new(0, 4)),
+    ] {
+        match eval_exact(&parse(expr)?, Inexact::Error) {
+            Ok(value) => return Err(anyhow!("Expected NotExact error, got {value}")),
+            Err(e) => assert_eq!(
+                e,
+                ParseError::NotExact {
+                    operation: operation.to_string(),
+                    span,
+                }
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_irrational_falls_back_to_float() -> Result<()> {
+    let value = eval_exact(&parse("1/3 + sqrt(2)")?, Inexact::Float)?;
+    assert!(!value.is_exact());
+    assert!((value.to_f64() - (1.0 / 3.0 + 2f64.sqrt())).abs() < 1e-12);
+    assert_eq!(value, Exact::Float(1.0 / 3.0 + 2f64.sqrt()));
+
+    let value = eval_exact(&parse("sqrt(4) + 1/3")?, Inexact::Float)?;
+    assert!(value.is_exact());
+    assert_eq!(value.to_string(), "7/3");
+    Ok(())
+}
+
+#[test]
+fn test_replaced_builtins_are_called() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.functions_mut()
+        .register("abs", Arity::Exact(1), |args| Ok(args[0] * 100.0));
+    ctx.functions_mut()
+        .register("sqrt", Arity::Exact(1), |args| Ok(args[0].abs().sqrt()));
+    let env = Environment::new();
+    let options = DecimalOptions::new(2, Rounding::HalfEven);
+
+    let expr = parse("abs(2)")?;
+    assert_eq!(ctx.eval_with(&expr, &env)?, 200.0);
+    assert_eq!(
+        ctx.eval_exact(&expr, &env, Inexact::Float)?.to_string(),
+        "200"
+    );
+    assert_eq!(ctx.eval_integer(&expr, &env)?, Integer::Approximate(200.0));
+    assert_eq!(
+        ctx.eval_decimal(&expr, &env, &options)?.to_string(),
+        "200.00"
+    );
+
+    let expr = parse("sqrt(-16)")?;
+    assert_eq!(
+        ctx.eval_exact(&expr, &env, Inexact::Float)?,
+        Exact::Float(4.0)
+    );
+    assert_eq!(ctx.eval_decimal(&expr, &env, &options)?.to_string(), "4.00");
+    Ok(())
+}
+
+#[test]
+fn test_huge_powers_fall_back_to_float() -> Result<()> {
+    let value = eval_exact(&parse("(2^100000)^100000")?, Inexact::Float)?;
+    assert_eq!(value, Exact::Float(f64::INFINITY));
+    let value = eval_exact(&parse("(1/3)^1000000000")?, Inexact::Float)?;
+    assert_eq!(value, Exact::Float(0.0));
+    match eval_exact(&parse("(2^100000)^100000")?, Inexact::Error) {
+        Ok(value) => return Err(anyhow!("Expected NotExact error, got {value}")),
+        Err(e) => assert!(matches!(e, ParseError::NotExact { .. })),
+    }
+    // powers of 0, 1 and -1 stay exact however large the exponent
+    assert_eq!(exact("(-1)^1000000001 + 0^1000000000")?, "-1");
+    assert_eq!(exact("2^100000 / 2^99999")?, "2");
+    Ok(())
+}
+
+#[test]
+fn test_exact_errors() -> Result<()> {
+    match eval_exact(&parse("1 / (1/3 - 1/3)")?, Inexact::Float) {
+        Ok(_) => return Err(anyhow!("Expected DivisionByZero error")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::DivisionByZero {
+                span: Span::new(4, 15)
+            }
+        ),
+    }
+    match eval_exact(&parse("sqrt(-4)")?, Inexact::Float) {
+        Ok(_) => return Err(anyhow!("Expected DomainError error")),
+        Err(e) => assert!(matches!(e, ParseError::DomainError { .. })),
+    }
+    match eval_exact(&parse("(-3)!")?, Inexact::Float) {
+        Ok(_) => return Err(anyhow!("Expected DomainError error")),
+        Err(e) => assert!(matches!(e, ParseError::DomainError { .. })),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_exact_with_variables() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.set_constant("vat", 0.2);
+    let env: Environment = [("price", 19.99)].into_iter().collect();
+    let value = ctx.eval_exact(&parse("price * (1 + vat)")?, &env, Inexact::Error)?;
+    assert_eq!(value.to_string(), "5997/250");
+    Ok(())
+}
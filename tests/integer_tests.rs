@@ -0,0 +1,102 @@
+use anyhow::{Result, anyhow};
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Context, Environment, Integer, ParseError, Span, eval_integer, parse,
+};
+use num_bigint::BigInt;
+
+fn integer(expr: &str) -> Result<Integer> {
+    Ok(eval_integer(&parse(expr)?)?)
+}
+
+#[test]
+fn test_no_overflow() -> Result<()> {
+    assert_eq!(
+        integer("2^200")?.to_string(),
+        "1606938044258990275541962092341162602522202993782792835301376"
+    );
+    assert_eq!(
+        integer("30!")?.to_string(),
+        "265252859812191058636308480000000"
+    );
+    assert_eq!(
+        integer("2^64 + 1")?,
+        Integer::Exact("18446744073709551617".parse::<BigInt>()?)
+    );
+    let product = -BigInt::from(3).pow(80u32) - 1;
+    assert_eq!(integer("-(3^40) * 3^40 - 1")?, Integer::Exact(product));
+    Ok(())
+}
+
+#[test]
+fn test_integer_operators() -> Result<()> {
+    assert_eq!(integer("17 % 5")?, Integer::Exact(BigInt::from(2)));
+    assert_eq!(integer("-17 // 5")?, Integer::Exact(BigInt::from(-4)));
+    assert_eq!(integer("2^0")?, Integer::Exact(BigInt::from(1)));
+    assert_eq!(
+        integer("max(abs(-7), min(3, 4), 25!)")?.to_string(),
+        "15511210043330985984000000"
+    );
+    assert_eq!(
+        integer("sqrt(144) + round(2)")?,
+        Integer::Exact(BigInt::from(14))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_division_and_fractions_approximate() -> Result<()> {
+    assert_eq!(integer("6 / 3")?, Integer::Approximate(2.0));
+    assert_eq!(integer("2^-1")?, Integer::Approximate(0.5));
+    assert_eq!(integer("1.5 * 2")?, Integer::Approximate(3.0));
+    assert_eq!(
+        integer("2 * pi")?,
+        Integer::Approximate(2.0 * std::f64::consts::PI)
+    );
+    assert_eq!(integer("sqrt(2)")?, Integer::Approximate(2f64.sqrt()));
+
+    let value = integer("30! / 7")?;
+    assert!(!value.is_exact());
+    assert_eq!(value.as_bigint(), None);
+    assert!((value.to_f64() - 265252859812191058636308480000000.0 / 7.0).abs() < 1e20);
+    Ok(())
+}
+
+#[test]
+fn test_huge_powers_approximate() -> Result<()> {
+    assert_eq!(
+        integer("(2^100000)^100000")?,
+        Integer::Approximate(f64::INFINITY)
+    );
+    let value = integer("2^100000")?;
+    assert!(value.is_exact());
+    assert_eq!(value.as_bigint().map(|n| n.bits()), Some(100001));
+    Ok(())
+}
+
+#[test]
+fn test_integer_errors() -> Result<()> {
+    match eval_integer(&parse("5 % (2 - 2)")?) {
+        Ok(_) => return Err(anyhow!("Expected DivisionByZero error")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::DivisionByZero {
+                span: Span::new(4, 11)
+            }
+        ),
+    }
+    match eval_integer(&parse("(-3)!")?) {
+        Ok(_) => return Err(anyhow!("Expected DomainError error")),
+        Err(e) => assert!(matches!(e, ParseError::DomainError { .. })),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_integer_with_variables() -> Result<()> {
+    let ctx = Context::default();
+    let env: Environment = [("n", 25.0), ("rate", 0.5)].into_iter().collect();
+    let value = ctx.eval_integer(&parse("n! * n")?, &env)?;
+    assert_eq!(value.to_string(), "387780251083274649600000000");
+    assert!(!ctx.eval_integer(&parse("n * rate")?, &env)?.is_exact());
+    Ok(())
+}
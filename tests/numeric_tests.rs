@@ -0,0 +1,223 @@
+use anyhow::{Result, anyhow};
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Arity, Context, DecimalOptions, Environment, EvalError, Expr, Inexact, Numeric, ParseError,
+    Rounding, Span, eval, eval_as, eval_complex, eval_decimal, eval_exact, eval_integer, parse,
+};
+
+/// A fixed-point backend with three decimal places, as a host application might plug in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Milli(i64);
+
+impl Numeric for Milli {
+    fn from_literal(literal: &str) -> Option<Self> {
+        let (int, frac) = literal.split_once('.').unwrap_or((literal, ""));
+        if frac.len() > 3 || (int.is_empty() && frac.is_empty()) {
+            return None;
+        }
+        let int: i64 = if int.is_empty() { 0 } else { int.parse().ok()? };
+        let frac: i64 = format!("{frac:0<3}").parse().ok()?;
+        Some(Milli(int * 1000 + frac))
+    }
+
+    fn from_f64(x: f64) -> Self {
+        Milli((x * 1000.0).round() as i64)
+    }
+
+    fn to_f64(&self) -> f64 {
+        self.0 as f64 / 1000.0
+    }
+
+    fn add(&self, rhs: &Self) -> Self {
+        Milli(self.0 + rhs.0)
+    }
+
+    fn sub(&self, rhs: &Self) -> Self {
+        Milli(self.0 - rhs.0)
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        Milli(self.0 * rhs.0 / 1000)
+    }
+
+    fn div(&self, rhs: &Self) -> Result<Self, EvalError> {
+        if rhs.0 == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok(Milli(self.0 * 1000 / rhs.0))
+    }
+
+    fn pow(&self, exp: &Self) -> Result<Self, EvalError> {
+        self.to_f64().pow(&exp.to_f64()).map(Milli::from_f64)
+    }
+
+    fn sqrt(&self) -> Result<Self, EvalError> {
+        Numeric::sqrt(&self.to_f64()).map(Milli::from_f64)
+    }
+
+    fn ln(&self) -> Result<Self, EvalError> {
+        Numeric::ln(&self.to_f64()).map(Milli::from_f64)
+    }
+
+    // truncated rather than rounded, as in a table of constants
+    fn constant(name: &str) -> Option<Self> {
+        match name {
+            "pi" => Some(Milli(3141)),
+            "e" => Some(Milli(2718)),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_f64_is_the_default() -> Result<()> {
+    for source in [
+        "1 + 2 * 3",
+        "2^0.5 - 7 % 3",
+        "-7 // 2",
+        "log(2, 8) + 5!",
+        "sin(pi / 6)",
+    ] {
+        let expr = parse(source)?;
+        assert_eq!(eval_as::<f64>(&expr)?, eval(&expr)?, "{source}");
+    }
+    Ok(())
+}
+
+#[test]
+fn test_f32() -> Result<()> {
+    assert_eq!(eval_as::<f32>(&parse("0.1 + 0.2")?)?, 0.1f32 + 0.2f32);
+    assert_eq!(eval_as::<f32>(&parse("1 / 3")?)?, 1.0f32 / 3.0);
+    assert_eq!(
+        eval_as::<f32>(&parse("sqrt(2) * ln(10)")?)?,
+        2f32.sqrt() * 10f32.ln()
+    );
+    assert_eq!(eval_as::<f32>(&parse("2^10 - 7 % 4 + 7 // 2")?)?, 1024.0);
+    assert_eq!(eval_as::<f32>(&parse("5!")?)?, 120.0);
+    // functions without an f32 implementation are computed in f64
+    assert!((eval_as::<f32>(&parse("sin(pi / 6)")?)? - 0.5).abs() < 1e-6);
+    Ok(())
+}
+
+#[test]
+fn test_f32_errors() -> Result<()> {
+    match eval_as::<f32>(&parse("1 / (2 - 2)")?) {
+        Ok(_) => return Err(anyhow!("Expected DivisionByZero error")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::DivisionByZero {
+                span: Span::new(4, 11)
+            }
+        ),
+    }
+    match eval_as::<f32>(&parse("0^-1")?) {
+        Ok(_) => return Err(anyhow!("Expected DivisionByZero error")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::DivisionByZero {
+                span: Span::new(0, 1)
+            }
+        ),
+    }
+    match eval_as::<f32>(&parse("2 + sqrt(-4)")?) {
+        Ok(_) => return Err(anyhow!("Expected DomainError error")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::DomainError {
+                function: "sqrt".to_string(),
+                arg: -4.0,
+                span: Span::new(4, 12),
+            }
+        ),
+    }
+    match eval_as::<f32>(&parse("(-8)^0.5")?) {
+        Ok(_) => return Err(anyhow!("Expected DomainError error")),
+        Err(e) => assert!(matches!(e, ParseError::DomainError { .. })),
+    }
+    Ok(())
+}
+
+// the error kind of `expr` in every kind of evaluation
+fn error_kinds(expr: &Expr) -> Vec<Option<&'static str>> {
+    let options = DecimalOptions::new(2, Rounding::HalfEven);
+    vec![
+        eval(expr).err().map(|e| e.kind()),
+        eval_as::<f32>(expr).err().map(|e| e.kind()),
+        eval_as::<Milli>(expr).err().map(|e| e.kind()),
+        eval_exact(expr, Inexact::Float).err().map(|e| e.kind()),
+        eval_integer(expr).err().map(|e| e.kind()),
+        eval_decimal(expr, &options).err().map(|e| e.kind()),
+    ]
+}
+
+#[test]
+fn test_pow_errors_agree() -> Result<()> {
+    for source in ["0^-1", "pow(0, -1)", "pow(0, -0.5)"] {
+        let expr = parse(source)?;
+        assert_eq!(error_kinds(&expr), [Some("DivisionByZero"); 6], "{source}");
+        let kind = eval_complex(&expr).err().map(|e| e.kind());
+        assert_eq!(kind, Some("DivisionByZero"), "{source}");
+    }
+    for source in ["(-8)^0.5", "pow(-8, 0.5)"] {
+        assert_eq!(
+            error_kinds(&parse(source)?),
+            [Some("DomainError"); 6],
+            "{source}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_custom_backend() -> Result<()> {
+    assert_eq!(eval_as::<Milli>(&parse("1.25 * 4 - 0.5")?)?, Milli(4500));
+    assert_eq!(eval_as::<Milli>(&parse("10 / 3")?)?, Milli(3333));
+    assert_eq!(eval_as::<Milli>(&parse("sqrt(2)")?)?, Milli(1414));
+    assert_eq!(eval_as::<Milli>(&parse("-(2 + 1)")?)?, Milli(-3000));
+    assert_eq!(eval_as::<Milli>(&parse("7 % 4")?)?, Milli(3000));
+    match eval_as::<Milli>(&parse("0.0001 + 1")?) {
+        Ok(_) => return Err(anyhow!("Expected InvalidNumber error")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::InvalidNumber {
+                literal: "0.0001".to_string(),
+                span: Span::new(0, 6)
+            }
+        ),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_builtin_constants() -> Result<()> {
+    assert_eq!(eval_as::<Milli>(&parse("2 * pi")?)?, Milli(6282));
+    assert_eq!(eval_as::<Milli>(&parse("e")?)?, Milli(2718));
+    // constants without a value of their own come from f64
+    assert_eq!(eval_as::<Milli>(&parse("tau")?)?, Milli(6283));
+
+    // a variable or a constant set by the host hides the built-in one
+    let mut ctx = Context::default();
+    let env: Environment = [("e", 2.5)].into_iter().collect();
+    ctx.set_constant("pi", 3.0);
+    assert_eq!(ctx.eval_as::<Milli>(&parse("pi + e")?, &env)?, Milli(5500));
+    ctx.set_constant("pi", std::f64::consts::PI);
+    assert_eq!(ctx.eval_as::<Milli>(&parse("pi")?, &env)?, Milli(3141));
+    Ok(())
+}
+
+#[test]
+fn test_context_and_registered_functions() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.functions_mut()
+        .register("tax", Arity::Exact(1), |args| Ok(args[0] * 0.2));
+    // a replaced built-in is called like any registered function
+    ctx.functions_mut()
+        .register("sqrt", Arity::Exact(1), |args| Ok(args[0].abs().sqrt()));
+    let env: Environment = [("price", 19.5)].into_iter().collect();
+
+    let expr = parse("price + tax(price) + sqrt(-16)")?;
+    assert_eq!(ctx.eval_as::<f32>(&expr, &env)?, 19.5 + 3.9 + 4.0);
+    assert_eq!(ctx.eval_as::<Milli>(&expr, &env)?, Milli(27400));
+    assert!(!ctx.functions().get("sqrt").unwrap().is_builtin());
+    assert!(ctx.functions().get("ln").unwrap().is_builtin());
+    Ok(())
+}
@@ -1,6 +1,10 @@
 use anyhow::{Result, anyhow};
 use arythemetic_expressions_parser_kharchenko_kma::ParseError;
 use arythemetic_expressions_parser_kharchenko_kma::parse_expression;
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Arity, BinaryOp, Context, Environment, Expr, MAX_DEPTH, MAX_NESTING, ParseOptions, Span,
+    UnaryOp, eval, eval_with, parse, parse_statement, parse_with, strip_comment,
+};
 
 #[test]
 fn test_single() -> Result<()> {
@@ -124,19 +128,19 @@ fn test_sqrt() -> Result<()> {
 fn test_log_invalid_arg() -> Result<()> {
     match parse_expression("log(0)") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected DomainError error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::DomainError { .. }));
         }
     }
 
     match parse_expression("log(-5)") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected DomainError error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::DomainError { .. }));
         }
     }
 
@@ -147,10 +151,10 @@ fn test_log_invalid_arg() -> Result<()> {
 fn test_sqrt_invalid_argument() -> Result<()> {
     match parse_expression("sqrt(-1)") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected DomainError error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::DomainError { .. }));
         }
     }
     Ok(())
@@ -160,19 +164,19 @@ fn test_sqrt_invalid_argument() -> Result<()> {
 fn test_invalid_operator() -> Result<()> {
     match parse_expression("1++2") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected UnexpectedToken error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::UnexpectedToken { .. }));
         }
     }
 
     match parse_expression("1*/2") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected UnexpectedToken error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::UnexpectedToken { .. }));
         }
     }
     Ok(())
@@ -182,10 +186,10 @@ fn test_invalid_operator() -> Result<()> {
 fn test_invalid_empty() -> Result<()> {
     match parse_expression("") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected EmptyInput error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::EmptyInput { .. }));
         }
     }
     Ok(())
@@ -195,36 +199,940 @@ fn test_invalid_empty() -> Result<()> {
 fn test_invalid_unbalanced_brackets() -> Result<()> {
     match parse_expression("(1+2") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected UnbalancedParen error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::UnbalancedParen { .. }));
         }
     }
     Ok(())
 }
 
 #[test]
-fn test_invalid_minus_after_minus() -> Result<()> {
-    match parse_expression("-(-1)") {
+fn test_minus_after_minus() -> Result<()> {
+    let r = parse_expression("-(-1)")?;
+    assert_eq!(r.result, 1.0);
+    Ok(())
+}
+
+#[test]
+fn test_letters() -> Result<()> {
+    match parse_expression("abc") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected UnknownVariable error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::UnknownVariable { .. }));
+        }
+    }
+
+    match parse_expression("2 abc") {
+        Ok(_) => {
+            return Err(anyhow!("Expected UnexpectedToken error"));
+        }
+        Err(e) => {
+            assert!(matches!(e, ParseError::UnexpectedToken { .. }));
         }
     }
     Ok(())
 }
 
 #[test]
-fn test_letters() -> Result<()> {
-    match parse_expression("abc") {
+fn test_parse_precedence_tree() -> Result<()> {
+    let expr = parse("1 + 2 * 3")?;
+    assert_eq!(
+        expr,
+        Expr::Binary {
+            op: BinaryOp::Add,
+            lhs: Box::new(Expr::Number {
+                value: 1.0,
+                literal: "1".to_string(),
+                span: Span::new(0, 1),
+            }),
+            rhs: Box::new(Expr::Binary {
+                op: BinaryOp::Mul,
+                lhs: Box::new(Expr::Number {
+                    value: 2.0,
+                    literal: "2".to_string(),
+                    span: Span::new(4, 5),
+                }),
+                rhs: Box::new(Expr::Number {
+                    value: 3.0,
+                    literal: "3".to_string(),
+                    span: Span::new(8, 9),
+                }),
+                span: Span::new(4, 9),
+            }),
+            span: Span::new(0, 9),
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_left_associative() -> Result<()> {
+    let expr = parse("8 - 4 - 2")?;
+    assert_eq!(eval(&expr)?, 2.0);
+    assert_eq!(expr.operators(), vec![BinaryOp::Sub, BinaryOp::Sub]);
+    assert_eq!(expr.operands(), vec![8.0, 4.0, 2.0]);
+    Ok(())
+}
+
+#[test]
+fn test_parse_group_and_call() -> Result<()> {
+    let expr = parse("-(sqrt(4))")?;
+    assert_eq!(
+        expr,
+        Expr::Unary {
+            op: UnaryOp::Neg,
+            operand: Box::new(Expr::Group {
+                inner: Box::new(Expr::Call {
+                    name: "sqrt".to_string(),
+                    args: vec![Expr::Number {
+                        value: 4.0,
+                        literal: "4".to_string(),
+                        span: Span::new(7, 8),
+                    }],
+                    span: Span::new(2, 9),
+                }),
+                span: Span::new(1, 10),
+            }),
+            span: Span::new(0, 10),
+        }
+    );
+    assert_eq!(eval(&expr)?, -2.0);
+    Ok(())
+}
+
+#[test]
+fn test_eval_division_by_zero() -> Result<()> {
+    let expr = parse("1 / (2 - 2)")?;
+    match eval(&expr) {
+        Ok(_) => {
+            return Err(anyhow!("Expected DivisionByZero error"));
+        }
+        Err(e) => {
+            assert!(matches!(e, ParseError::DivisionByZero { .. }));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_function_in_sum() -> Result<()> {
+    let r = parse_expression("2 + sqrt(4)")?;
+    assert_eq!(r.result, 4.0);
+
+    let r2 = parse_expression("sqrt(9) - 1")?;
+    assert_eq!(r2.result, 2.0);
+    Ok(())
+}
+
+#[test]
+fn test_function_product() -> Result<()> {
+    let r = parse_expression("log(3) * log(5)")?;
+    assert!((r.result - 3.0f64.ln() * 5.0f64.ln()).abs() < 1e-10);
+    Ok(())
+}
+
+#[test]
+fn test_nested_functions() -> Result<()> {
+    let r = parse_expression("sqrt(sqrt(16))")?;
+    assert_eq!(r.result, 2.0);
+
+    let r2 = parse_expression("2 * (1 + sqrt(log(1) + 16))")?;
+    assert_eq!(r2.result, 10.0);
+    Ok(())
+}
+
+#[test]
+fn test_negated_function() -> Result<()> {
+    let r = parse_expression("-sqrt(9) + 1")?;
+    assert_eq!(r.result, -2.0);
+    Ok(())
+}
+
+#[test]
+fn test_function_domain_inside_expression() -> Result<()> {
+    match parse_expression("1 + log(0)") {
+        Ok(_) => {
+            return Err(anyhow!("Expected DomainError error"));
+        }
+        Err(e) => {
+            assert!(matches!(e, ParseError::DomainError { .. }));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_error_unexpected_token_span() -> Result<()> {
+    let e = parse("1 + * 2").unwrap_err();
+    assert_eq!(e.span(), Span::new(4, 5));
+    match e {
+        ParseError::UnexpectedToken {
+            found, expected, ..
+        } => {
+            assert_eq!(found, "*");
+            assert!(expected.contains(&"number".to_string()));
+        }
+        other => return Err(anyhow!("Expected UnexpectedToken error, got {other:?}")),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_error_unexpected_end() -> Result<()> {
+    let e = parse("1 +").unwrap_err();
+    assert_eq!(e.span(), Span::new(3, 3));
+    assert!(e.to_string().starts_with("unexpected end of input at 3..3"));
+    Ok(())
+}
+
+#[test]
+fn test_error_unbalanced_span() -> Result<()> {
+    assert_eq!(
+        parse("(1 + (2)").unwrap_err(),
+        ParseError::UnbalancedParen {
+            span: Span::new(0, 1)
+        }
+    );
+    assert_eq!(
+        parse("1 + 2)").unwrap_err(),
+        ParseError::UnbalancedParen {
+            span: Span::new(5, 6)
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_error_division_by_zero_span() -> Result<()> {
+    let e = parse_expression("1 / (2 - 2)").unwrap_err();
+    assert_eq!(
+        e,
+        ParseError::DivisionByZero {
+            span: Span::new(4, 11)
+        }
+    );
+    assert_eq!(e.to_string(), "division by zero at 4..11");
+    Ok(())
+}
+
+#[test]
+fn test_error_domain() -> Result<()> {
+    let e = parse_expression("sqrt(1 - 5)").unwrap_err();
+    assert_eq!(
+        e,
+        ParseError::DomainError {
+            function: "sqrt".to_string(),
+            arg: -4.0,
+            span: Span::new(0, 11),
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_error_unknown_function() -> Result<()> {
+    let e = parse_expression("2 * foo(1)").unwrap_err();
+    assert_eq!(
+        e,
+        ParseError::UnknownFunction {
+            name: "foo".to_string(),
+            span: Span::new(4, 10),
+        }
+    );
+    assert_eq!(e.to_string(), "unknown function 'foo' at 4..10");
+    Ok(())
+}
+
+#[test]
+fn test_error_empty_input() -> Result<()> {
+    assert_eq!(
+        parse("   ").unwrap_err(),
+        ParseError::EmptyInput {
+            span: Span::new(0, 3)
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_error_invalid_number() -> Result<()> {
+    assert_eq!(
+        parse("2 + 1.2.3").unwrap_err(),
+        ParseError::InvalidNumber {
+            literal: "1.2.3".to_string(),
+            span: Span::new(4, 9),
+        }
+    );
+    assert_eq!(
+        parse("2 + 1.2.3e-5").unwrap_err(),
+        ParseError::InvalidNumber {
+            literal: "1.2.3e-5".to_string(),
+            span: Span::new(4, 12),
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_scientific_notation() -> Result<()> {
+    assert_eq!(parse_expression("1e5")?.result, 1e5);
+    assert_eq!(parse_expression("1e-5")?.result, 1e-5);
+    assert_eq!(parse_expression("2 * 1e3")?.result, 2000.0);
+    assert_eq!(parse_expression("1e5 + 1")?.result, 100001.0);
+    assert_eq!(parse_expression("1.5E+2 - .5e1")?.result, 145.0);
+    match parse("2.5e3")? {
+        Expr::Number {
+            value,
+            literal,
+            span,
+        } => {
+            assert_eq!(value, 2500.0);
+            assert_eq!(literal, "2.5e3");
+            assert_eq!(span, Span::new(0, 5));
+        }
+        other => return Err(anyhow!("Expected a number, got {other:?}")),
+    }
+
+    // without digits after it, `e` is the constant
+    assert_eq!(parse_expression("2*e")?.result, 2.0 * std::f64::consts::E);
+    assert_eq!(
+        parse_expression("2^e")?.result,
+        2f64.powf(std::f64::consts::E)
+    );
+    // and `2e` is a number followed by the name `e`, not a broken exponent
+    match parse("2e") {
+        Ok(_) => return Err(anyhow!("Expected UnexpectedToken error")),
+        Err(e) => assert!(matches!(
+            e,
+            ParseError::UnexpectedToken { ref found, span, .. }
+                if found == "e" && span == Span::new(1, 2)
+        )),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_power() -> Result<()> {
+    let r = parse_expression("2^3")?;
+    assert_eq!(r.result, 8.0);
+
+    let r2 = parse_expression("2 ** 3")?;
+    assert_eq!(r2.result, 8.0);
+    Ok(())
+}
+
+#[test]
+fn test_power_right_associative() -> Result<()> {
+    let r = parse_expression("2^3^2")?;
+    assert_eq!(r.result, 512.0);
+
+    let r2 = parse_expression("2**3**2")?;
+    assert_eq!(r2.result, 512.0);
+    Ok(())
+}
+
+#[test]
+fn test_power_precedence() -> Result<()> {
+    let r = parse_expression("2 * 3^2")?;
+    assert_eq!(r.result, 18.0);
+
+    let r2 = parse_expression("2^2 * 3")?;
+    assert_eq!(r2.result, 12.0);
+
+    let r3 = parse_expression("(2 + 1)^2")?;
+    assert_eq!(r3.result, 9.0);
+    Ok(())
+}
+
+#[test]
+fn test_power_unary_minus() -> Result<()> {
+    let r = parse_expression("-2^2")?;
+    assert_eq!(r.result, -4.0);
+
+    let r2 = parse_expression("(-2)^2")?;
+    assert_eq!(r2.result, 4.0);
+
+    let r3 = parse_expression("2^-1")?;
+    assert_eq!(r3.result, 0.5);
+    Ok(())
+}
+
+#[test]
+fn test_power_with_functions() -> Result<()> {
+    let r = parse_expression("sqrt(16)^2 - 2^sqrt(4)")?;
+    assert_eq!(r.result, 12.0);
+    Ok(())
+}
+
+#[test]
+fn test_power_invalid() -> Result<()> {
+    match parse_expression("(-8)^0.5") {
+        Ok(_) => {
+            return Err(anyhow!("Expected DomainError error"));
+        }
+        Err(e) => {
+            assert!(matches!(e, ParseError::DomainError { .. }));
+        }
+    }
+
+    match parse_expression("0^-1") {
         Ok(_) => {
-            return Err(anyhow!("Expected InvalidExpression error"));
+            return Err(anyhow!("Expected DivisionByZero error"));
         }
         Err(e) => {
-            assert!(matches!(e, ParseError::InvalidExpression));
+            assert!(matches!(e, ParseError::DivisionByZero { .. }));
+        }
+    }
+
+    match parse_expression("2^^3") {
+        Ok(_) => {
+            return Err(anyhow!("Expected UnexpectedToken error"));
+        }
+        Err(e) => {
+            assert!(matches!(e, ParseError::UnexpectedToken { .. }));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_remainder() -> Result<()> {
+    let r = parse_expression("7 % 3")?;
+    assert_eq!(r.result, 1.0);
+
+    let r2 = parse_expression("-7 % 3")?;
+    assert_eq!(r2.result, -1.0);
+
+    let r3 = parse_expression("5.5 % 2")?;
+    assert_eq!(r3.result, 1.5);
+    Ok(())
+}
+
+#[test]
+fn test_floor_division() -> Result<()> {
+    let r = parse_expression("7 // 2")?;
+    assert_eq!(r.result, 3.0);
+
+    let r2 = parse_expression("-7 // 2")?;
+    assert_eq!(r2.result, -4.0);
+    Ok(())
+}
+
+#[test]
+fn test_term_level_precedence() -> Result<()> {
+    let r = parse_expression("1 + 10 % 4 * 3")?;
+    assert_eq!(r.result, 7.0);
+
+    let r2 = parse_expression("20 // 3 // 2")?;
+    assert_eq!(r2.result, 3.0);
+
+    let r3 = parse_expression("2^3 % 5")?;
+    assert_eq!(r3.result, 3.0);
+    Ok(())
+}
+
+#[test]
+fn test_operators_in_order() -> Result<()> {
+    let r = parse_expression("9 // 2 % 3 / 1")?;
+    assert_eq!(
+        r.operators,
+        vec![BinaryOp::FloorDiv, BinaryOp::Rem, BinaryOp::Div]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_modulo_by_zero() -> Result<()> {
+    match parse_expression("5 % 0") {
+        Ok(_) => {
+            return Err(anyhow!("Expected DivisionByZero error"));
+        }
+        Err(e) => {
+            assert!(matches!(e, ParseError::DivisionByZero { .. }));
+        }
+    }
+
+    match parse_expression("5 // (1 - 1)") {
+        Ok(_) => {
+            return Err(anyhow!("Expected DivisionByZero error"));
+        }
+        Err(e) => {
+            assert!(matches!(e, ParseError::DivisionByZero { .. }));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_factorial() -> Result<()> {
+    let r = parse_expression("5!")?;
+    assert_eq!(r.result, 120.0);
+
+    let r2 = parse_expression("0!")?;
+    assert_eq!(r2.result, 1.0);
+
+    let r3 = parse_expression("(1 + 2)! + 1")?;
+    assert_eq!(r3.result, 7.0);
+    Ok(())
+}
+
+#[test]
+fn test_factorial_precedence() -> Result<()> {
+    let r = parse_expression("-3!")?;
+    assert_eq!(r.result, -6.0);
+
+    let r2 = parse_expression("2^3!")?;
+    assert_eq!(r2.result, 64.0);
+
+    let r3 = parse_expression("3!!")?;
+    assert_eq!(r3.result, 720.0);
+
+    let r4 = parse_expression("2 * 3!")?;
+    assert_eq!(r4.result, 12.0);
+    Ok(())
+}
+
+#[test]
+fn test_factorial_gamma() -> Result<()> {
+    let r = parse_expression("0.5!")?;
+    assert!((r.result - std::f64::consts::PI.sqrt() / 2.0).abs() < 1e-12);
+
+    let r2 = parse_expression("4.5!")?;
+    assert!((r2.result - 52.34277778455352).abs() < 1e-9);
+
+    // large arguments overflow to infinity like integers do, not to NaN
+    let r3 = parse_expression("170.5!")?;
+    assert!((r3.result / 9.483367566824735e307 - 1.0).abs() < 1e-10);
+    assert_eq!(parse_expression("1000.5!")?.result, f64::INFINITY);
+    assert_eq!(parse_expression("1000!")?.result, f64::INFINITY);
+    Ok(())
+}
+
+#[test]
+fn test_factorial_negative() -> Result<()> {
+    match parse_expression("(-1)!") {
+        Ok(_) => {
+            return Err(anyhow!("Expected DomainError error"));
+        }
+        Err(e) => {
+            assert!(matches!(e, ParseError::DomainError { .. }));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_percent_mode() -> Result<()> {
+    let options = ParseOptions {
+        percent: true,
+        ..ParseOptions::default()
+    };
+
+    let expr = parse_with("50%", &options)?;
+    assert_eq!(eval(&expr)?, 0.5);
+
+    let expr2 = parse_with("200 * 15% - 2", &options)?;
+    assert_eq!(eval(&expr2)?, 28.0);
+
+    let expr3 = parse_with("(10 + 10)% * 10", &options)?;
+    assert_eq!(eval(&expr3)?, 2.0);
+    Ok(())
+}
+
+#[test]
+fn test_percent_mode_disables_modulo() -> Result<()> {
+    let options = ParseOptions {
+        percent: true,
+        ..ParseOptions::default()
+    };
+    assert!(matches!(
+        parse_with("7 % 3", &options),
+        Err(ParseError::UnexpectedToken { .. })
+    ));
+
+    // without the mode `%` stays the remainder operator
+    assert!(matches!(
+        parse("50%"),
+        Err(ParseError::UnexpectedToken { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_constants() -> Result<()> {
+    let r = parse_expression("2*pi")?;
+    assert_eq!(r.result, std::f64::consts::TAU);
+
+    let r2 = parse_expression("e^2")?;
+    assert!((r2.result - std::f64::consts::E.powi(2)).abs() < 1e-12);
+
+    let r3 = parse_expression("log(e)")?;
+    assert_eq!(r3.result, 1.0);
+
+    let r4 = parse_expression("tau / 2 - pi")?;
+    assert_eq!(r4.result, 0.0);
+    Ok(())
+}
+
+#[test]
+fn test_constant_ast() -> Result<()> {
+    let expr = parse("-pi")?;
+    assert_eq!(
+        expr,
+        Expr::Unary {
+            op: UnaryOp::Neg,
+            operand: Box::new(Expr::Ident {
+                name: "pi".to_string(),
+                span: Span::new(1, 3),
+            }),
+            span: Span::new(0, 3),
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn test_non_finite_constants() -> Result<()> {
+    let r = parse_expression("-inf")?;
+    assert_eq!(r.result, f64::NEG_INFINITY);
+
+    let r2 = parse_expression("nan + 1")?;
+    assert!(r2.result.is_nan());
+    Ok(())
+}
+
+#[test]
+fn test_strict_context() -> Result<()> {
+    let strict = Context::strict();
+    assert_eq!(strict.eval(&parse("2 * pi")?)?, std::f64::consts::TAU);
+
+    match strict.eval(&parse("1 / inf")?) {
+        Ok(_) => {
+            return Err(anyhow!("Expected UnknownVariable error"));
+        }
+        Err(e) => {
+            assert_eq!(
+                e,
+                ParseError::UnknownVariable {
+                    name: "inf".to_string(),
+                    span: Span::new(4, 7),
+                }
+            );
+        }
+    }
+    assert!(strict.eval(&parse("nan")?).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_custom_constant() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.set_constant("g", 9.81);
+    assert_eq!(ctx.eval(&parse("2 * g")?)?, 19.62);
+
+    ctx.remove_constant("pi");
+    assert!(ctx.eval(&parse("pi")?).is_err());
+    Ok(())
+}
+
+fn approx(expr: &str, expected: f64) -> Result<()> {
+    let r = parse_expression(expr)?;
+    if (r.result - expected).abs() > 1e-12 {
+        return Err(anyhow!("{expr} = {}, expected {expected}", r.result));
+    }
+    Ok(())
+}
+
+fn expect_domain_error(expr: &str, function: &str) -> Result<()> {
+    match parse_expression(expr) {
+        Ok(r) => Err(anyhow!("Expected DomainError for {expr}, got {}", r.result)),
+        Err(ParseError::DomainError { function: f, .. }) if f == function => Ok(()),
+        Err(e) => Err(anyhow!("Expected DomainError for {expr}, got {e:?}")),
+    }
+}
+
+#[test]
+fn test_trigonometric_functions() -> Result<()> {
+    approx("sin(pi / 2)", 1.0)?;
+    approx("cos(pi)", -1.0)?;
+    approx("tan(pi / 4)", 1.0)?;
+    approx("asin(1)", std::f64::consts::FRAC_PI_2)?;
+    approx("acos(1)", 0.0)?;
+    approx("atan(1) * 4", std::f64::consts::PI)?;
+    Ok(())
+}
+
+#[test]
+fn test_hyperbolic_functions() -> Result<()> {
+    approx("sinh(0)", 0.0)?;
+    approx("cosh(0)", 1.0)?;
+    approx("tanh(1)", 1.0f64.tanh())?;
+    approx("cosh(1)^2 - sinh(1)^2", 1.0)?;
+    Ok(())
+}
+
+#[test]
+fn test_exponential_and_logarithms() -> Result<()> {
+    approx("exp(1)", std::f64::consts::E)?;
+    approx("ln(e^3)", 3.0)?;
+    approx("log10(1000)", 3.0)?;
+    approx("log2(8)", 3.0)?;
+    approx("cbrt(-27)", -3.0)?;
+    Ok(())
+}
+
+#[test]
+fn test_rounding_functions() -> Result<()> {
+    approx("abs(-2.5)", 2.5)?;
+    approx("floor(-2.5)", -3.0)?;
+    approx("ceil(-2.5)", -2.0)?;
+    approx("round(2.5)", 3.0)?;
+    approx("trunc(-2.7)", -2.0)?;
+    approx("sign(-3) + sign(0) + sign(7)", 0.0)?;
+    Ok(())
+}
+
+#[test]
+fn test_function_domains() -> Result<()> {
+    expect_domain_error("asin(2)", "asin")?;
+    expect_domain_error("acos(-1.5)", "acos")?;
+    expect_domain_error("ln(0)", "ln")?;
+    expect_domain_error("log10(-1)", "log10")?;
+    expect_domain_error("log2(0)", "log2")?;
+    expect_domain_error("tan(pi / 2)", "tan")?;
+    expect_domain_error("tan(3 * pi / 2)", "tan")?;
+    Ok(())
+}
+
+#[test]
+fn test_multi_argument_functions() -> Result<()> {
+    approx("max(1, 2, 3)", 3.0)?;
+    approx("min(4, -1, 2) + max(7)", 6.0)?;
+    approx("log(2, 8)", 3.0)?;
+    approx("log(10, 1000)", 3.0)?;
+    approx("atan2(1, 1)", std::f64::consts::FRAC_PI_4)?;
+    approx("hypot(3, 4)", 5.0)?;
+    approx("pow(2, 10)", 1024.0)?;
+    approx("clamp(5, 0, 1) + clamp(-5, 0, 1) + clamp(0.5, 0, 1)", 1.5)?;
+    Ok(())
+}
+
+#[test]
+fn test_arguments_are_expressions() -> Result<()> {
+    approx("max(1 + 1, sqrt(16), 2^1.5)", 4.0)?;
+    approx("2 * hypot(max(3, 1), (2 + 2))", 10.0)?;
+    Ok(())
+}
+
+#[test]
+fn test_call_ast_arguments() -> Result<()> {
+    match parse("atan2(pi, 1)")? {
+        Expr::Call { name, args, span } => {
+            assert_eq!(name, "atan2");
+            assert_eq!(args.len(), 2);
+            assert_eq!(args[1].span(), Span::new(10, 11));
+            assert_eq!(span, Span::new(0, 12));
+        }
+        other => return Err(anyhow!("Expected a call, got {other:?}")),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_arity_mismatch() -> Result<()> {
+    assert_eq!(
+        parse_expression("log(1, 2, 3)").unwrap_err(),
+        ParseError::ArityMismatch {
+            function: "log".to_string(),
+            expected: Arity::Range(1, 2),
+            found: 3,
+            span: Span::new(0, 12),
+        }
+    );
+
+    let e = parse_expression("atan2(1)").unwrap_err();
+    assert_eq!(e.to_string(), "atan2 expects 2 arguments, found 1 at 0..8");
+
+    let e2 = parse_expression("max()").unwrap_err();
+    assert_eq!(
+        e2.to_string(),
+        "max expects at least 1 argument, found 0 at 0..5"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_multi_argument_domains() -> Result<()> {
+    expect_domain_error("log(1, 5)", "log")?;
+    expect_domain_error("log(2, -5)", "log")?;
+    expect_domain_error("pow(-8, 1/3)", "pow")?;
+    expect_domain_error("clamp(1, 2, 0)", "clamp")?;
+    Ok(())
+}
+
+#[test]
+fn test_invalid_argument_list() -> Result<()> {
+    for src in ["max(1,)", "max(,1)", "max(1 2)"] {
+        match parse(src) {
+            Ok(_) => return Err(anyhow!("Expected UnexpectedToken error for {src}")),
+            Err(e) => assert!(matches!(e, ParseError::UnexpectedToken { .. })),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_variables() -> Result<()> {
+    let env: Environment = [("x", 3.0), ("rate", 0.2), ("total_2024", 1000.0)]
+        .into_iter()
+        .collect();
+    assert_eq!(eval_with(&parse("x^2 + 1")?, &env)?, 10.0);
+    assert_eq!(eval_with(&parse("total_2024 * (1 + rate)")?, &env)?, 1200.0);
+    assert_eq!(
+        eval_with(&parse("max(x, rate) * pi")?, &env)?,
+        3.0 * std::f64::consts::PI
+    );
+    Ok(())
+}
+
+#[test]
+fn test_same_formula_different_inputs() -> Result<()> {
+    let formula = parse("price * (1 + rate)")?;
+    let mut env = Environment::new();
+    env.set("rate", 0.5);
+    for (price, expected) in [(10.0, 15.0), (20.0, 30.0)] {
+        env.set("price", price);
+        assert_eq!(eval_with(&formula, &env)?, expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_variable_shadows_constant() -> Result<()> {
+    let env: Environment = [("e", 2.0)].into_iter().collect();
+    assert_eq!(eval_with(&parse("e^2")?, &env)?, 4.0);
+
+    let mut ctx = Context::strict();
+    ctx.set_constant("vat", 0.2);
+    let env2: Environment = [("vat", 0.1)].into_iter().collect();
+    assert_eq!(ctx.eval_with(&parse("100 * vat")?, &env2)?, 10.0);
+    assert_eq!(ctx.eval(&parse("100 * vat")?)?, 20.0);
+    Ok(())
+}
+
+#[test]
+fn test_unknown_variable() -> Result<()> {
+    let env: Environment = [("x", 1.0)].into_iter().collect();
+    let e = eval_with(&parse("x + y * 2")?, &env).unwrap_err();
+    assert_eq!(
+        e,
+        ParseError::UnknownVariable {
+            name: "y".to_string(),
+            span: Span::new(4, 5),
+        }
+    );
+    assert_eq!(e.to_string(), "unknown variable 'y' at 4..5");
+    Ok(())
+}
+
+#[test]
+fn test_strip_comment() -> Result<()> {
+    assert_eq!(strip_comment("# reference values"), "");
+    assert_eq!(strip_comment("sqrt(16) # => 4"), "sqrt(16) ");
+    assert_eq!(strip_comment("// section"), "");
+    assert_eq!(strip_comment("\t// indented"), "");
+    assert_eq!(strip_comment("7 // 2"), "7 // 2");
+    assert_eq!(strip_comment("7 // 2 # floor division"), "7 // 2 ");
+    assert_eq!(strip_comment("1 + 2"), "1 + 2");
+
+    let line = "2 * 3 # six";
+    assert_eq!(parse_expression(strip_comment(line))?.result, 6.0);
+    Ok(())
+}
+
+#[test]
+fn test_deeply_nested_brackets() -> Result<()> {
+    // each level used to parse its contents twice, doubling the time per level
+    let depth = 30;
+    let source = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+    assert_eq!(eval(&parse(&source)?)?, 1.0);
+
+    let source = format!("{}2{}^2", "(-".repeat(depth), ")".repeat(depth));
+    assert_eq!(eval(&parse(&source)?)?, 4.0);
+
+    let source = format!("{}1{}", "sqrt(".repeat(depth), ")".repeat(depth));
+    assert_eq!(parse_expression(&source)?.result, 1.0);
+    Ok(())
+}
+
+#[test]
+fn test_nesting_limit() -> Result<()> {
+    // (source at the limit, one level more, where the extra level starts)
+    let n = MAX_NESTING;
+    for (at_limit, too_deep, start) in [
+        (
+            format!("{}1{}", "(".repeat(n), ")".repeat(n)),
+            format!("{}1{}", "(".repeat(n + 1), ")".repeat(n + 1)),
+            n,
+        ),
+        (
+            format!("{}1{}", "abs(".repeat(n), ")".repeat(n)),
+            format!("{}1{}", "abs(".repeat(n + 1), ")".repeat(n + 1)),
+            4 * n,
+        ),
+        (
+            format!("{}1", "-".repeat(n)),
+            format!("{}1", "-".repeat(n + 1)),
+            n,
+        ),
+        (
+            format!("{}1", "1^".repeat(n)),
+            format!("{}1", "1^".repeat(n + 1)),
+            2 * n + 1,
+        ),
+    ] {
+        assert_eq!(eval(&parse(&at_limit)?)?.abs(), 1.0, "{at_limit}");
+        match parse_statement(&too_deep) {
+            Ok(_) => return Err(anyhow!("Expected TooDeep error")),
+            Err(e) => assert_eq!(
+                e,
+                ParseError::TooDeep {
+                    span: Span::new(start, start + 1)
+                }
+            ),
+        }
+    }
+    // far past the limit the parser stops instead of overflowing the stack
+    let source = format!("{}1{}", "(".repeat(20_000), ")".repeat(20_000));
+    match parse(&source) {
+        Ok(_) => return Err(anyhow!("Expected TooDeep error")),
+        Err(e) => assert_eq!(e.code(), "E014"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_depth_limit() -> Result<()> {
+    // every `+` puts the sum before it one level deeper into the tree
+    let source = format!("1{}", " + 1".repeat(MAX_DEPTH - 1));
+    assert_eq!(eval(&parse(&source)?)?, MAX_DEPTH as f64);
+    for source in [
+        format!("1{}", " + 1".repeat(MAX_DEPTH)),
+        format!("1{}", "!".repeat(MAX_DEPTH)),
+        format!("x = 1{}", " * 1".repeat(50_000)),
+    ] {
+        match parse_statement(&source) {
+            Ok(_) => return Err(anyhow!("Expected TooDeep error")),
+            Err(e) => assert_eq!(
+                e,
+                ParseError::TooDeep {
+                    span: Span::new(source.find('1').unwrap_or(0), source.len())
+                }
+            ),
         }
     }
     Ok(())
@@ -0,0 +1,150 @@
+use anyhow::{Result, anyhow};
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Arity, Context, EvalError, FunctionRegistry, ParseError, Span, parse, parse_expression,
+};
+
+fn pricing_context() -> Context {
+    let mut ctx = Context::default();
+    let functions = ctx.functions_mut();
+    functions.register("tax", Arity::Exact(1), |args| Ok(args[0] * 0.2));
+    functions.register("avg", Arity::AtLeast(1), |args| {
+        Ok(args.iter().sum::<f64>() / args.len() as f64)
+    });
+    functions.register("discount", Arity::Range(1, 2), |args| {
+        let rate = args.get(1).copied().unwrap_or(0.1);
+        if !(0.0..=1.0).contains(&rate) {
+            return Err(EvalError::Domain { arg: rate });
+        }
+        Ok(args[0] * (1.0 - rate))
+    });
+    functions.register("per_unit", Arity::Exact(2), |args| {
+        if args[1] == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok(args[0] / args[1])
+    });
+    functions.register("lookup", Arity::Exact(1), |args| {
+        Err(EvalError::Custom(format!("no price for item {}", args[0])))
+    });
+    ctx
+}
+
+#[test]
+fn test_custom_functions() -> Result<()> {
+    let ctx = pricing_context();
+    assert_eq!(ctx.eval(&parse("100 + tax(100)")?)?, 120.0);
+    assert_eq!(ctx.eval(&parse("avg(1, 2, 3, 6)")?)?, 3.0);
+    assert_eq!(ctx.eval(&parse("discount(200)")?)?, 180.0);
+    assert_eq!(ctx.eval(&parse("discount(200, 0.5) + sqrt(16)")?)?, 104.0);
+    Ok(())
+}
+
+#[test]
+fn test_custom_function_arity() -> Result<()> {
+    let ctx = pricing_context();
+    assert_eq!(
+        ctx.eval(&parse("tax(1, 2)")?).unwrap_err(),
+        ParseError::ArityMismatch {
+            function: "tax".to_string(),
+            expected: Arity::Exact(1),
+            found: 2,
+            span: Span::new(0, 9),
+        }
+    );
+    assert!(matches!(
+        ctx.eval(&parse("avg()")?),
+        Err(ParseError::ArityMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_custom_function_errors() -> Result<()> {
+    let ctx = pricing_context();
+    assert_eq!(
+        ctx.eval(&parse("discount(10, 2)")?).unwrap_err(),
+        ParseError::DomainError {
+            function: "discount".to_string(),
+            arg: 2.0,
+            span: Span::new(0, 15),
+        }
+    );
+    assert_eq!(
+        ctx.eval(&parse("1 + per_unit(5, 0)")?).unwrap_err(),
+        ParseError::DivisionByZero {
+            span: Span::new(4, 18)
+        }
+    );
+
+    let e = ctx.eval(&parse("lookup(7)")?).unwrap_err();
+    assert_eq!(e.to_string(), "lookup failed: no price for item 7 at 0..9");
+    assert_eq!(e.code(), "E010");
+    Ok(())
+}
+
+#[test]
+fn test_custom_function_not_real() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.functions_mut()
+        .register("half_root", Arity::Exact(1), |args| {
+            if args[0] < 0.0 {
+                return Err(EvalError::NotReal);
+            }
+            Ok(args[0].sqrt() / 2.0)
+        });
+    assert_eq!(ctx.eval(&parse("half_root(16)")?)?, 2.0);
+    match ctx.eval(&parse("2 * half_root(-4)")?) {
+        Ok(_) => return Err(anyhow!("Expected NotReal error")),
+        Err(e) => assert_eq!(
+            e,
+            ParseError::NotReal {
+                operation: "half_root".to_string(),
+                span: Span::new(4, 17),
+            }
+        ),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_override_and_remove_builtin() -> Result<()> {
+    let mut ctx = Context::default();
+    ctx.functions_mut()
+        .register("log", Arity::Exact(1), |args| Ok(args[0].log10()));
+    assert_eq!(ctx.eval(&parse("log(100)")?)?, 2.0);
+
+    assert!(ctx.functions_mut().remove("sqrt"));
+    assert!(!ctx.functions().contains("sqrt"));
+    match ctx.eval(&parse("sqrt(4)")?) {
+        Ok(_) => return Err(anyhow!("Expected UnknownFunction error")),
+        Err(e) => assert!(matches!(e, ParseError::UnknownFunction { .. })),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_empty_registry() -> Result<()> {
+    let mut registry = FunctionRegistry::new();
+    registry.register("double", Arity::Exact(1), |args| Ok(args[0] * 2.0));
+
+    let mut ctx = Context::default();
+    ctx.set_functions(registry);
+    assert_eq!(ctx.functions().names(), vec!["double"]);
+    assert_eq!(ctx.eval(&parse("double(2)")?)?, 4.0);
+    assert!(ctx.eval(&parse("sin(0)")?).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_default_registry_is_used_by_parse_expression() -> Result<()> {
+    let registry = FunctionRegistry::default();
+    assert!(registry.contains("sqrt"));
+    assert!(registry.contains("max"));
+    assert_eq!(
+        registry.get("atan2").map(|f| f.arity()),
+        Some(Arity::Exact(2))
+    );
+
+    assert_eq!(parse_expression("max(sqrt(16), 3)")?.result, 4.0);
+    Ok(())
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "serde")]
+
+use anyhow::Result;
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Expr, ParseError, ParseResult, Statement, eval, parse, parse_expression, parse_statement,
+};
+
+#[test]
+fn test_expr_round_trip() -> Result<()> {
+    let expr = parse("-sqrt(2 + x)^2 * max(1, 2, 3)! // 4")?;
+    let json = serde_json::to_string(&expr)?;
+    let back: Expr = serde_json::from_str(&json)?;
+    assert_eq!(back, expr);
+    Ok(())
+}
+
+#[test]
+fn test_cached_formula_evaluates() -> Result<()> {
+    let json = serde_json::to_string(&parse("(1 + 2) * pi")?)?;
+    let formula: Expr = serde_json::from_str(&json)?;
+    assert_eq!(eval(&formula)?, 3.0 * std::f64::consts::PI);
+    Ok(())
+}
+
+#[test]
+fn test_expr_json_shape() -> Result<()> {
+    let json = serde_json::to_value(parse("1 + 2")?)?;
+    assert_eq!(json["Binary"]["op"], "Add");
+    assert_eq!(json["Binary"]["lhs"]["Number"]["value"], 1.0);
+    assert_eq!(json["Binary"]["span"]["start"], 0);
+    assert_eq!(json["Binary"]["span"]["end"], 5);
+    Ok(())
+}
+
+#[test]
+fn test_statement_round_trip() -> Result<()> {
+    for source in ["rate = 0.2", "sqrt(16) == 4", "ans / 2"] {
+        let statement = parse_statement(source)?;
+        let back: Statement = serde_json::from_str(&serde_json::to_string(&statement)?)?;
+        assert_eq!(back, statement);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_parse_result_round_trip() -> Result<()> {
+    let result = parse_expression("2 + 3 * 4")?;
+    let back: ParseResult = serde_json::from_str(&serde_json::to_string(&result)?)?;
+    assert_eq!(back.result, 14.0);
+    assert_eq!(back.operands, result.operands);
+    assert_eq!(back.operators, result.operators);
+    Ok(())
+}
+
+#[test]
+fn test_error_round_trip() -> Result<()> {
+    for source in [
+        "1 +", "(1", "1 / 0", "log(0)", "sqr(4)", "", "1.2.3", "foo", "atan2(1)",
+    ] {
+        let error = parse_expression(source).unwrap_err();
+        let back: ParseError = serde_json::from_str(&serde_json::to_string(&error)?)?;
+        assert_eq!(back, error);
+        assert_eq!(back.to_string(), error.to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,155 @@
+use anyhow::{Result, anyhow};
+use arythemetic_expressions_parser_kharchenko_kma::{
+    Context, DEFAULT_TOLERANCE, Expr, ParseError, Session, Span, Statement, parse_statement,
+};
+
+#[test]
+fn test_parse_assignment() -> Result<()> {
+    match parse_statement("rate = 0.2")? {
+        Statement::Assign { name, value, span } => {
+            assert_eq!(name, "rate");
+            assert!(matches!(value, Expr::Number { value, .. } if value == 0.2));
+            assert_eq!(span, Span::new(0, 10));
+        }
+        other => return Err(anyhow!("Expected an assignment, got {other:?}")),
+    }
+    assert!(matches!(parse_statement("rate * 2")?, Statement::Expr(_)));
+    Ok(())
+}
+
+#[test]
+fn test_assignment_needs_a_name_and_a_value() -> Result<()> {
+    for input in ["2 = 3", "x =", "= 1", "x = y = 1"] {
+        match parse_statement(input) {
+            Ok(statement) => {
+                return Err(anyhow!("Expected UnexpectedToken error, got {statement:?}"));
+            }
+            Err(e) => assert!(
+                matches!(e, ParseError::UnexpectedToken { .. }),
+                "{input}: {e}"
+            ),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_variables_persist_across_lines() -> Result<()> {
+    let mut session = Session::new();
+    assert_eq!(session.run("rate = 0.2")?, 0.2);
+    assert_eq!(session.run("price = 50")?, 50.0);
+    assert_eq!(session.run("price * (1 + rate)")?, 60.0);
+    session.run("price = price * 2")?;
+    assert_eq!(session.run("price * (1 + rate)")?, 120.0);
+    Ok(())
+}
+
+#[test]
+fn test_previous_result() -> Result<()> {
+    let mut session = Session::new();
+    assert_eq!(session.ans(), None);
+    session.run("2 + 3")?;
+    assert_eq!(session.run("ans * 2")?, 10.0);
+    assert_eq!(session.run("_ + 1")?, 11.0);
+    session.run("x = 4")?;
+    assert_eq!(session.run("ans")?, 4.0);
+    assert_eq!(session.ans(), Some(4.0));
+    Ok(())
+}
+
+#[test]
+fn test_failed_line_keeps_state() -> Result<()> {
+    let mut session = Session::new();
+    session.run("x = 1")?;
+    match session.run("x = 1 / 0") {
+        Ok(_) => return Err(anyhow!("Expected DivisionByZero error")),
+        Err(e) => assert!(matches!(e, ParseError::DivisionByZero { .. })),
+    }
+    assert_eq!(session.env().get("x"), Some(1.0));
+    assert_eq!(session.ans(), Some(1.0));
+    Ok(())
+}
+
+#[test]
+fn test_ans_before_first_result() -> Result<()> {
+    let mut session = Session::new();
+    match session.run("ans + 1") {
+        Ok(_) => return Err(anyhow!("Expected UnknownVariable error")),
+        Err(e) => assert!(matches!(e, ParseError::UnknownVariable { .. })),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_session_with_context() -> Result<()> {
+    let mut ctx = Context::strict();
+    ctx.set_constant("g", 9.81);
+    let mut session = Session::with_context(ctx);
+    session.run("t = 2")?;
+    assert_eq!(session.run("g * t")?, 19.62);
+    assert!(session.run("inf").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_parse_expectation() -> Result<()> {
+    match parse_statement("sqrt(16) == 4")? {
+        Statement::Expect {
+            value,
+            expected,
+            span,
+        } => {
+            assert!(matches!(value, Expr::Call { .. }));
+            assert!(matches!(expected, Expr::Number { value, .. } if value == 4.0));
+            assert_eq!(span, Span::new(0, 13));
+        }
+        other => return Err(anyhow!("Expected an expectation, got {other:?}")),
+    }
+    let mut session = Session::new();
+    assert_eq!(session.run("2 + 3 * 4 == 14")?, 14.0);
+    assert_eq!(session.ans(), Some(14.0));
+    match session.run("2 + 3 * 4 == 15") {
+        Ok(_) => return Err(anyhow!("Expected ExpectationFailed error")),
+        Err(e) => {
+            assert_eq!(
+                e,
+                ParseError::ExpectationFailed {
+                    actual: 14.0,
+                    expected: 15.0,
+                    span: Span::new(0, 15),
+                }
+            );
+            assert_eq!(e.code(), "E013");
+        }
+    }
+    // a failed expectation leaves the session unchanged
+    assert!(session.run("ans + 1 == 16").is_err());
+    assert_eq!(session.ans(), Some(14.0));
+    Ok(())
+}
+
+#[test]
+fn test_expectation_tolerance() -> Result<()> {
+    let mut session = Session::new();
+    assert_eq!(session.run("0.1 + 0.2 == 0.3")?, 0.1 + 0.2);
+    assert_eq!(
+        session.run("sqrt(2) * sqrt(2) == 2")?,
+        2f64.sqrt() * 2f64.sqrt()
+    );
+    // relative to the expected value above 1
+    assert!(session.run("1e12 + 1 == 1e12").is_ok());
+    assert!(session.run("1e-3 == 1e-3 + 1e-8").is_err());
+    assert!(session.run("nan == nan").is_ok());
+
+    let mut exact = Session::new().tolerance(0.0);
+    match exact.run("0.1 + 0.2 == 0.3") {
+        Ok(_) => return Err(anyhow!("Expected ExpectationFailed error")),
+        Err(e) => assert!(matches!(e, ParseError::ExpectationFailed { .. })),
+    }
+    let mut loose = Session::new().tolerance(1e-3);
+    assert!(loose.run("pi == 3.1416").is_ok());
+    assert!(session.matches(1.0 + 1e-10, 1.0));
+    assert!(!exact.matches(1.0 + 1e-10, 1.0));
+    assert_eq!(DEFAULT_TOLERANCE, 1e-9);
+    Ok(())
+}